@@ -0,0 +1,31 @@
+//! Search an address in an arbitrary database
+//!
+//! Input layout: 1 byte mode, 16 bytes address (the first 4 are used for IPv4), database bytes.
+#![no_main]
+
+use czdb_rs::searcher::{DbSearcher, SearchMode};
+use libfuzzer_sys::fuzz_target;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// Key of the seed databases in `fuzz/seeds` (base64 of "0123456789abcdef")
+const KEY: &str = "MDEyMzQ1Njc4OWFiY2RlZg==";
+
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 17 {
+        return;
+    }
+    let mode = if data[0] & 1 == 0 { SearchMode::Memory } else { SearchMode::BTree };
+    let addr: [u8; 16] = data[1..17].try_into().unwrap();
+
+    let Ok(searcher) = DbSearcher::with_mode(data[17..].to_vec(), KEY, mode) else {
+        return;
+    };
+
+    let v4 = Ipv4Addr::new(addr[0], addr[1], addr[2], addr[3]);
+    let v6 = Ipv6Addr::from(addr);
+    let _ = searcher.search(&v4.to_string());
+    let _ = searcher.search(&v6.to_string());
+    let _ = searcher.search_record(&v4.to_string());
+    let _ = searcher.search_batch_v4(&[u32::from(v4), 0, u32::MAX]);
+    let _ = searcher.search_batch_v6(&[u128::from(v6), 0, u128::MAX]);
+});
@@ -0,0 +1,14 @@
+//! Parse arbitrary bytes as a database in both modes
+#![no_main]
+
+use czdb_rs::searcher::{DbSearcher, SearchMode};
+use libfuzzer_sys::fuzz_target;
+
+/// Key of the seed databases in `fuzz/seeds` (base64 of "0123456789abcdef")
+const KEY: &str = "MDEyMzQ1Njc4OWFiY2RlZg==";
+
+fuzz_target!(|data: &[u8]| {
+    for mode in [SearchMode::Memory, SearchMode::BTree] {
+        let _ = DbSearcher::with_mode(data.to_vec(), KEY, mode);
+    }
+});
@@ -114,6 +114,62 @@ async function benchRust(name: string, mode: number, dbPath: string, testFile: s
     }
 }
 
+function ipv4ToU32(ip: string): number {
+    return ip.split('.').reduce((acc, octet) => ((acc << 8) | parseInt(octet, 10)) >>> 0, 0);
+}
+
+function ipv6ToBytes(ip: string, out: Uint8Array, offset: number) {
+    const [head, tail = ''] = ip.split('::');
+    const headParts = head ? head.split(':') : [];
+    const tailParts = tail ? tail.split(':') : [];
+    const parts = ip.includes('::')
+        ? [...headParts, ...Array(8 - headParts.length - tailParts.length).fill('0'), ...tailParts]
+        : headParts;
+    parts.forEach((part, i) => {
+        const v = parseInt(part || '0', 16);
+        out[offset + i * 2] = v >> 8;
+        out[offset + i * 2 + 1] = v & 0xff;
+    });
+}
+
+// Typed-array batch lookup (searchBatchU32 / searchBatchV6); address conversion is not timed
+async function benchTypedBatch(name: string, mode: number, dbPath: string, testFile: string, isV6: boolean) {
+    console.log(`\nBenchmarking ${name} (mode: ${mode === 0 ? 'Memory' : 'BTree'}, typed batch)...`);
+
+    try {
+        const Searcher = await getSearcherClass();
+        const searcher = Searcher.new_with_mode(readFileSync(dbPath), CZDB_SECRET, mode);
+        const ips = loadIps(testFile);
+
+        let packed: Uint32Array | Uint8Array;
+        if (isV6) {
+            packed = new Uint8Array(ips.length * 16);
+            ips.forEach((ip, i) => ipv6ToBytes(ip, packed as Uint8Array, i * 16));
+        } else {
+            packed = Uint32Array.from(ips, ipv4ToU32);
+        }
+
+        const start = performance.now();
+        const { ids, regions } = isV6 ? searcher.searchBatchV6(packed) : searcher.searchBatchU32(packed);
+        const end = performance.now();
+
+        const totalTime = end - start;
+        const avgTime = totalTime / ips.length;
+        console.log(`  -> Processed ${ids.length} IPs (${regions.length} distinct regions) in ${totalTime.toFixed(2)}ms. Avg: ${(avgTime * 1000).toFixed(4)}us/ip`);
+
+        results.push({
+            name,
+            mode: mode === 0 ? 'Memory' : 'BTree',
+            totalTime: parseFloat(totalTime.toFixed(2)),
+            avgTime: parseFloat(avgTime.toFixed(4)),
+            count: ips.length,
+            outputFile: '-'
+        });
+    } catch (e) {
+        console.error(`  -> Failed:`, e);
+    }
+}
+
 // Benchmark native czdb library for comparison
 async function benchNativeCzdb(name: string, dbPath: string, testFile: string, outputFile: string) {
     if (!czdbNative) {
@@ -231,6 +287,10 @@ await benchRust(
     `${OUTPUT_DIR}/wasm_ipv6_btree.txt`
 );
 
+// Typed-array batch benchmarks
+await benchTypedBatch('WASM IPv4 U32', 0, 'czdb/cz88_public_v4.czdb', 'tests/IPV4.txt', false);
+await benchTypedBatch('WASM IPv6 Bytes', 0, 'czdb/cz88_public_v6.czdb', 'tests/IPV6.txt', true);
+
 // Native czdb benchmarks (if available)
 await benchNativeCzdb(
     'Native IPv4',
@@ -0,0 +1,307 @@
+//! Command line tool for CZDB databases
+//!
+//! ```text
+//! czdb verify <database> [--key <base64> | --key-file <path>] [--mode memory|btree|direct] [--direct-table <path>] [--json]
+//! czdb build-direct <database> <output> [--key <base64> | --key-file <path>]
+//! czdb list <database> [--key <base64> | --key-file <path>] [--format cidr|nftables|ipset|iptables|bird|bird2]
+//!           [--country <name>] [--province <name>] [--city <name>] [--isp <name>] [--prefix] [--invert]
+//!           [--name <name>] [--table <table>] [--target <target>] [--destination] [--route <route>]
+//! ```
+//!
+//! The key defaults to the `CZDB_SECRET` environment variable.
+//! Exit status: 0 = database is intact / table or list written, 1 = problems found, 2 = usage or load error.
+
+use czdb_rs::searcher::{
+    CzdbKey, DbSearcher, DirectTable, IpType, ListFormat, ListOptions, RegionFilter, RegionMatch, SearchMode, VerifyReport,
+};
+use std::process::ExitCode;
+
+const USAGE: &str = "Usage: czdb verify <database> [--key <base64> | --key-file <path>] [--mode memory|btree|direct] [--direct-table <path>] [--json]
+       czdb build-direct <database> <output> [--key <base64> | --key-file <path>]
+       czdb list <database> [--key <base64> | --key-file <path>] [--format cidr|nftables|ipset|iptables|bird|bird2]
+                 [--country <name>] [--province <name>] [--city <name>] [--isp <name>] [--prefix] [--invert]
+                 [--name <name>] [--table <table>] [--target <target>] [--destination] [--route <route>]";
+
+/// Options only `verify` and only `list` accept; `--key` and `--key-file` apply to all
+const VERIFY_OPTIONS: &[&str] = &["--mode", "--direct-table", "--json"];
+const LIST_OPTIONS: &[&str] = &[
+    "--format", "--country", "--province", "--city", "--isp", "--prefix", "--invert", "--name", "--table", "--target",
+    "--destination", "--route",
+];
+
+struct Options {
+    /// Positional arguments: the database, then the output of `build-direct`
+    paths: Vec<String>,
+    key: CzdbKey,
+    mode: SearchMode,
+    direct_table: Option<String>,
+    json: bool,
+    /// Regions listed by `list`
+    filter: RegionFilter,
+    list: ListOptions,
+}
+
+/// Parse the arguments of `command`, rejecting options of the other subcommands
+fn parse_options(args: &[String], command: &str) -> Result<Options, String> {
+    let (positional, allowed) = match command {
+        "verify" => (1, VERIFY_OPTIONS),
+        "build-direct" => (2, &[][..]),
+        _ => (1, LIST_OPTIONS),
+    };
+    let mut paths = Vec::new();
+    let mut key = None;
+    let mut mode = SearchMode::Memory;
+    let mut direct_table = None;
+    let mut json = false;
+    let mut filter = RegionFilter::default();
+    let mut list = ListOptions::default();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let option = arg.as_str();
+        if [VERIFY_OPTIONS, LIST_OPTIONS].concat().contains(&option) && !allowed.contains(&option) {
+            return Err(format!("{} does not apply to {}", arg, command));
+        }
+        match option {
+            "--key" => key = Some(CzdbKey::from_base64(iter.next().ok_or("--key needs a value")?)),
+            "--key-file" => key = Some(CzdbKey::from_file(iter.next().ok_or("--key-file needs a value")?)),
+            "--mode" => {
+                mode = match iter.next().map(String::as_str) {
+                    Some("memory") => SearchMode::Memory,
+                    Some("btree") => SearchMode::BTree,
+                    Some("direct") => SearchMode::Direct,
+                    _ => return Err("--mode must be memory, btree or direct".to_string()),
+                }
+            }
+            "--direct-table" => direct_table = Some(iter.next().ok_or("--direct-table needs a value")?.clone()),
+            "--json" => json = true,
+            "--format" => list.format = iter.next().ok_or("--format needs a value")?.parse::<ListFormat>().map_err(|e| e.to_string())?,
+            "--country" => filter.country = Some(iter.next().ok_or("--country needs a value")?.clone()),
+            "--province" => filter.province = Some(iter.next().ok_or("--province needs a value")?.clone()),
+            "--city" => filter.city = Some(iter.next().ok_or("--city needs a value")?.clone()),
+            "--isp" => filter.isp = Some(iter.next().ok_or("--isp needs a value")?.clone()),
+            "--prefix" => filter.matching = RegionMatch::Prefix,
+            "--invert" => list.invert = true,
+            "--name" => list.name = Some(iter.next().ok_or("--name needs a value")?.clone()),
+            "--table" => list.table = iter.next().ok_or("--table needs a value")?.clone(),
+            "--target" => list.target = iter.next().ok_or("--target needs a value")?.clone(),
+            "--destination" => list.destination = true,
+            "--route" => list.route = iter.next().ok_or("--route needs a value")?.clone(),
+            _ if arg.starts_with("--") => return Err(format!("unknown option {}", arg)),
+            _ if paths.len() < positional => paths.push(arg.clone()),
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
+    }
+    if paths.is_empty() {
+        return Err("missing database path".to_string());
+    }
+    if paths.len() < positional {
+        return Err("missing output path".to_string());
+    }
+
+    Ok(Options {
+        paths,
+        key: key
+            .unwrap_or_else(CzdbKey::from_default_env)
+            .map_err(|e| format!("invalid key: {} ({})", e, e.code()))?,
+        mode,
+        direct_table,
+        json,
+        filter,
+        list,
+    })
+}
+
+/// Load the database named by the first positional argument
+fn load_searcher(options: &Options) -> Result<DbSearcher, String> {
+    let data = std::fs::read(&options.paths[0]).map_err(|e| e.to_string())?;
+    let searcher = match &options.direct_table {
+        Some(path) => DirectTable::load(path)
+            .and_then(|table| DbSearcher::with_direct_table(data, &options.key, table)),
+        None => DbSearcher::with_mode(data, &options.key, options.mode),
+    };
+    searcher.map_err(|e| format!("{} ({})", e, e.code()))
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("verify") => verify(&args[1..]),
+        Some("build-direct") => build_direct(&args[1..]),
+        Some("list") => list(&args[1..]),
+        _ => {
+            eprintln!("{}", USAGE);
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn verify(args: &[String]) -> ExitCode {
+    let options = match parse_options(args, "verify") {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("czdb: {}\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    let database = &options.paths[0];
+
+    let searcher = match load_searcher(&options) {
+        Ok(searcher) => searcher,
+        Err(e) => {
+            eprintln!("czdb: {}: {}", database, e);
+            return ExitCode::from(2);
+        }
+    };
+
+    let report = searcher.verify();
+    if options.json {
+        println!("{}", report_json(database, &report));
+    } else {
+        print_report(database, &report);
+    }
+
+    if report.is_ok() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::from(1)
+    }
+}
+
+fn build_direct(args: &[String]) -> ExitCode {
+    let mut options = match parse_options(args, "build-direct") {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("czdb: {}\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    options.mode = SearchMode::Direct;
+    let (database, output) = (&options.paths[0], &options.paths[1]);
+
+    let searcher = match load_searcher(&options) {
+        Ok(searcher) => searcher,
+        Err(e) => {
+            eprintln!("czdb: {}: {}", database, e);
+            return ExitCode::from(2);
+        }
+    };
+    let table = searcher.direct_table().expect("Direct mode searcher has a table");
+    if let Err(e) = table.save(output) {
+        eprintln!("czdb: {}: {} ({})", output, e, e.code());
+        return ExitCode::from(2);
+    }
+
+    println!(
+        "{}: {} /24 entries, {} split across ranges, written to {}",
+        database,
+        DirectTable::ENTRIES,
+        table.split_blocks(),
+        output
+    );
+    ExitCode::SUCCESS
+}
+
+fn list(args: &[String]) -> ExitCode {
+    let options = match parse_options(args, "list") {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("czdb: {}\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    let database = &options.paths[0];
+
+    let searcher = match load_searcher(&options) {
+        Ok(searcher) => searcher,
+        Err(e) => {
+            eprintln!("czdb: {}: {}", database, e);
+            return ExitCode::from(2);
+        }
+    };
+    match searcher.region_list(&options.filter, &options.list) {
+        Ok(list) => {
+            print!("{}", list);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("czdb: {}: {} ({})", database, e, e.code());
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn ip_type_name(ip_type: IpType) -> &'static str {
+    match ip_type {
+        IpType::Ipv4 => "IPv4",
+        IpType::Ipv6 => "IPv6",
+    }
+}
+
+fn print_report(database: &str, report: &VerifyReport) {
+    println!(
+        "{}: {}, {} records, {} regions, {} geo rows, {} header entries, {} boundaries checked",
+        database,
+        ip_type_name(report.ip_type),
+        report.records,
+        report.regions,
+        report.geo_rows,
+        report.header_entries,
+        report.boundaries_checked
+    );
+    if report.is_ok() {
+        println!("OK");
+        return;
+    }
+    for issue in &report.issues {
+        println!("[{}] offset {}: {}", issue.check.code(), issue.offset, issue.message);
+    }
+    if report.issue_count > report.issues.len() {
+        println!("... {} more", report.issue_count - report.issues.len());
+    }
+    println!("{} problem(s) found", report.issue_count);
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn report_json(database: &str, report: &VerifyReport) -> String {
+    let issues: Vec<String> = report
+        .issues
+        .iter()
+        .map(|issue| {
+            format!(
+                "{{\"check\":{},\"offset\":{},\"message\":{}}}",
+                json_string(issue.check.code()),
+                issue.offset,
+                json_string(&issue.message)
+            )
+        })
+        .collect();
+    format!(
+        "{{\"database\":{},\"ok\":{},\"ipType\":{},\"records\":{},\"regions\":{},\"geoRows\":{},\"headerEntries\":{},\"boundariesChecked\":{},\"issueCount\":{},\"issues\":[{}]}}",
+        json_string(database),
+        report.is_ok(),
+        json_string(ip_type_name(report.ip_type)),
+        report.records,
+        report.regions,
+        report.geo_rows,
+        report.header_entries,
+        report.boundaries_checked,
+        report.issue_count,
+        issues.join(",")
+    )
+}
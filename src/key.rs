@@ -0,0 +1,118 @@
+//! Validated database key
+//!
+//! CZDB files are encrypted with a 128-bit key distributed as base64 text.
+//! `CzdbKey` validates the key once, keeps only the raw bytes and wipes them
+//! from memory when dropped.
+
+use crate::decrypt::DecryptError;
+use crate::searcher::CzdbError;
+use base64::{engine::general_purpose, Engine as _};
+use std::path::Path;
+use std::str::FromStr;
+use zeroize::{ZeroizeOnDrop, Zeroizing};
+
+/// Environment variable read by [`CzdbKey::from_default_env`]
+pub const KEY_ENV_VAR: &str = "CZDB_SECRET";
+
+/// 128-bit database key, zeroized on drop
+#[derive(Clone, ZeroizeOnDrop)]
+pub struct CzdbKey([u8; 16]);
+
+impl CzdbKey {
+    /// Wrap raw key bytes
+    pub fn from_bytes(bytes: [u8; 16]) -> Self {
+        CzdbKey(bytes)
+    }
+
+    /// Wrap raw key bytes, which must be exactly 16 bytes long
+    pub fn from_slice(bytes: &[u8]) -> Result<Self, CzdbError> {
+        let bytes: [u8; 16] = bytes.try_into().map_err(|_| DecryptError::InvalidKeyLength)?;
+        Ok(CzdbKey(bytes))
+    }
+
+    /// Decode a standard base64 key; surrounding whitespace is ignored
+    pub fn from_base64(key: &str) -> Result<Self, CzdbError> {
+        let bytes = Zeroizing::new(general_purpose::STANDARD.decode(key.trim()).map_err(DecryptError::from)?);
+        Self::from_slice(&bytes)
+    }
+
+    /// Read a base64 key from an environment variable
+    pub fn from_env(var: &str) -> Result<Self, CzdbError> {
+        let value = Zeroizing::new(std::env::var(var).map_err(|e| {
+            std::io::Error::new(std::io::ErrorKind::NotFound, format!("{}: {}", var, e))
+        })?);
+        Self::from_base64(&value)
+    }
+
+    /// Read a base64 key from `CZDB_SECRET`
+    pub fn from_default_env() -> Result<Self, CzdbError> {
+        Self::from_env(KEY_ENV_VAR)
+    }
+
+    /// Read a base64 key from a text file, e.g. a mounted secret
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, CzdbError> {
+        let value = Zeroizing::new(std::fs::read_to_string(path)?);
+        Self::from_base64(&value)
+    }
+
+    pub(crate) fn as_bytes(&self) -> &[u8; 16] {
+        &self.0
+    }
+}
+
+/// Never prints the key material
+impl std::fmt::Debug for CzdbKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("CzdbKey(..)")
+    }
+}
+
+impl FromStr for CzdbKey {
+    type Err = CzdbError;
+
+    fn from_str(key: &str) -> Result<Self, Self::Err> {
+        Self::from_base64(key)
+    }
+}
+
+impl From<[u8; 16]> for CzdbKey {
+    fn from(bytes: [u8; 16]) -> Self {
+        CzdbKey(bytes)
+    }
+}
+
+/// Key argument accepted by the `DbSearcher` constructors:
+/// a [`CzdbKey`] or base64 text (`&str`, `String`)
+pub trait IntoCzdbKey {
+    fn into_czdb_key(self) -> Result<CzdbKey, CzdbError>;
+}
+
+impl IntoCzdbKey for CzdbKey {
+    fn into_czdb_key(self) -> Result<CzdbKey, CzdbError> {
+        Ok(self)
+    }
+}
+
+impl IntoCzdbKey for &CzdbKey {
+    fn into_czdb_key(self) -> Result<CzdbKey, CzdbError> {
+        Ok(self.clone())
+    }
+}
+
+impl IntoCzdbKey for &str {
+    fn into_czdb_key(self) -> Result<CzdbKey, CzdbError> {
+        CzdbKey::from_base64(self)
+    }
+}
+
+impl IntoCzdbKey for &String {
+    fn into_czdb_key(self) -> Result<CzdbKey, CzdbError> {
+        CzdbKey::from_base64(self)
+    }
+}
+
+impl IntoCzdbKey for String {
+    fn into_czdb_key(self) -> Result<CzdbKey, CzdbError> {
+        CzdbKey::from_base64(&Zeroizing::new(self))
+    }
+}
@@ -1,42 +1,241 @@
 mod decrypt;
+pub mod key;
 pub mod searcher;
+#[cfg(feature = "napi")]
+mod node;
 
+use js_sys::{Array, ArrayBuffer, Int32Array, Object, Promise, Reflect, Uint8Array};
 use wasm_bindgen::prelude::*;
-use crate::searcher::{DbSearcher, SearchMode};
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use crate::searcher::{BatchRegions, CzdbError, DbSearcher, Language, Location, RegionRecord, SearchMode};
+
+#[wasm_bindgen(typescript_custom_section)]
+const CZDB_RECORD_TS: &'static str = r#"
+/**
+ * Structured search result; missing levels are empty strings. Country,
+ * province and city are in the searcher's language (see `setLanguage`).
+ */
+export interface CzdbRecord {
+    /** Country, or the area of regions naming none, e.g. `亚太地区` */
+    country: string;
+    /** Province-level division; Chinese ones by short name, e.g. `广西` */
+    province: string;
+    /** Prefecture-level city; the municipality itself for its districts */
+    city: string;
+    isp: string;
+    /** First address of the matched range */
+    start: string;
+    /** Last address of the matched range */
+    end: string;
+    /** Region string as returned by `search` */
+    raw: string;
+    /** ISO 3166-1 alpha-2 country code, e.g. `US` */
+    countryCode: string;
+    /** ISO 3166-1 alpha-3 country code, e.g. `USA` */
+    countryAlpha3: string;
+    /** ISO 3166-2:CN code of a Chinese province-level division, e.g. `CN-GD` */
+    subdivisionCode: string;
+    /** Canonical carrier id, e.g. `chinatelecom`, `aliyun` */
+    carrier: string;
+    /** `residential`, `mobile`, `education`, `datacenter` or `unknown` */
+    ispCategory: string;
+}
+
+/**
+ * Error thrown by the searcher. `code` is stable across releases
+ * (`TRUNCATED`, `INVALID_FORMAT`, `CLIENT_ID_MISMATCH`, `INVALID_IP`, ...);
+ * `section` and `offset` locate the failing structure in the database file.
+ */
+export interface CzdbError extends Error {
+    name: "CzdbError";
+    code: string;
+    section?: string;
+    offset?: number;
+}
+
+/** Failed entry of `searchBatchDetailed` */
+export interface CzdbBatchError {
+    /** Position of the address in the input array */
+    index: number;
+    /** Stable error code, e.g. `INVALID_IP`, `IP_TYPE_MISMATCH`, `INVALID_FORMAT` */
+    code: string;
+    message: string;
+}
+
+/** Result of `searchBatchU32` / `searchBatchV6` */
+export interface CzdbRegionIds {
+    /** Index into `regions` per address; -1 = no match, -2 = corrupt record */
+    ids: Int32Array;
+    /** Distinct region strings */
+    regions: string[];
+}
+
+export interface CzdbBatchResult {
+    /** Region per input address, `null` where the lookup failed */
+    results: (string | null)[];
+    errors: CzdbBatchError[];
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "CzdbRecord")]
+    pub type CzdbRecordObject;
+
+    #[wasm_bindgen(typescript_type = "Array<CzdbRecord | undefined>")]
+    pub type CzdbRecordArray;
+
+    #[wasm_bindgen(typescript_type = "CzdbBatchResult")]
+    pub type CzdbBatchResultObject;
+
+    #[wasm_bindgen(typescript_type = "CzdbRegionIds")]
+    pub type CzdbRegionIdsObject;
+}
 
 #[wasm_bindgen]
 pub struct CzdbSearcher {
     inner: DbSearcher,
 }
 
+/// Convert a record into a plain `CzdbRecord` object.
+/// Every field comes from [`Location::parse`] localized into `language`, so the
+/// levels mean the same in every language; Chinese leaves the names as stored.
+fn record_to_js(record: &RegionRecord, language: Language) -> CzdbRecordObject {
+    let location = Location::parse(&record.region).localized(language);
+    let fields = location.record_fields();
+
+    let obj = Object::new();
+    let set = |k: &str, v: &str| {
+        Reflect::set(&obj, &JsValue::from_str(k), &JsValue::from_str(v)).unwrap_throw();
+    };
+    set("country", fields.country);
+    set("province", fields.province);
+    set("city", fields.city);
+    set("isp", fields.isp);
+    set("start", &record.start.to_string());
+    set("end", &record.end.to_string());
+    set("raw", &record.region);
+    set("countryCode", fields.country_code);
+    set("countryAlpha3", fields.country_alpha3);
+    set("subdivisionCode", fields.subdivision_code);
+    set("carrier", fields.carrier);
+    set("ispCategory", fields.isp_category);
+    obj.unchecked_into()
+}
+
+/// Language given to a record search, or the searcher's own
+fn language_from_js(searcher: &DbSearcher, language: Option<String>) -> Result<Language, JsValue> {
+    match language {
+        Some(tag) => tag.parse().map_err(to_js_error),
+        None => Ok(searcher.language()),
+    }
+}
+
+/// Build a JS `Error` carrying `code`, `section` and `offset` for a core error
+fn to_js_error(e: CzdbError) -> JsValue {
+    let error = js_error(e.code(), &e.to_string());
+    if let Some(section) = e.section() {
+        Reflect::set(&error, &"section".into(), &JsValue::from_str(&section.to_string())).unwrap_throw();
+    }
+    if let Some(offset) = e.offset() {
+        Reflect::set(&error, &"offset".into(), &JsValue::from(offset as f64)).unwrap_throw();
+    }
+    error.into()
+}
+
+/// Build a JS `Error` named `CzdbError` with the given code
+fn js_error(code: &str, message: &str) -> js_sys::Error {
+    let error = js_sys::Error::new(message);
+    error.set_name("CzdbError");
+    Reflect::set(&error, &"code".into(), &JsValue::from_str(code)).unwrap_throw();
+    error
+}
+
+fn region_ids_to_js(batch: BatchRegions) -> CzdbRegionIdsObject {
+    let regions: Array = batch.regions.iter().map(|r| JsValue::from_str(r)).collect();
+    let obj = Object::new();
+    Reflect::set(&obj, &"ids".into(), &Int32Array::from(&batch.ids[..])).unwrap_throw();
+    Reflect::set(&obj, &"regions".into(), &regions).unwrap_throw();
+    obj.unchecked_into()
+}
+
+/// Copy the database bytes out of an `ArrayBuffer` or any `ArrayBufferView`
+/// (`Uint8Array`, `Buffer`, `DataView`, ...) straight into WASM memory.
+fn bytes_from_js(data: &JsValue) -> Result<Vec<u8>, JsValue> {
+    let view = if let Some(buffer) = data.dyn_ref::<ArrayBuffer>() {
+        Uint8Array::new(buffer)
+    } else if let Some(bytes) = data.dyn_ref::<Uint8Array>() {
+        bytes.clone()
+    } else if ArrayBuffer::is_view(data) {
+        let buffer: ArrayBuffer = js_sys::Reflect::get(data, &"buffer".into())
+            .map_err(|_| js_error("INVALID_INPUT", "Invalid ArrayBufferView"))?
+            .unchecked_into();
+        let number = |name: &str| {
+            js_sys::Reflect::get(data, &name.into())
+                .ok()
+                .and_then(|v| v.as_f64())
+                .ok_or_else(|| js_error("INVALID_INPUT", &format!("Invalid ArrayBufferView {}", name)))
+        };
+        let offset = number("byteOffset")? as u32;
+        let length = number("byteLength")? as u32;
+        Uint8Array::new_with_byte_offset_and_length(&buffer, offset, length)
+    } else {
+        return Err(js_error("INVALID_INPUT", "Expected ArrayBuffer or Uint8Array").into());
+    };
+    Ok(view.to_vec())
+}
+
+/// Create a searcher from an `ArrayBuffer`, `Uint8Array` or a promise resolving
+/// to either (e.g. `fetch(url).then(r => r.arrayBuffer())`).
+///
+/// Works with the `web`, `bundler` and `deno` builds as well as edge runtimes
+/// where the database is fetched rather than read from disk.
+#[wasm_bindgen(js_name = createSearcher)]
+pub async fn create_searcher(
+    data: JsValue,
+    key: String,
+    mode: Option<u8>,
+) -> Result<CzdbSearcher, JsValue> {
+    let data = match data.dyn_into::<Promise>() {
+        Ok(promise) => JsFuture::from(promise)
+            .await
+            .map_err(|_| js_error("IO_ERROR", "Failed to load database"))?,
+        Err(data) => data,
+    };
+    let bytes = bytes_from_js(&data)?;
+    let search_mode = SearchMode::try_from(mode.unwrap_or(0))
+        .map_err(to_js_error)?;
+    let searcher = DbSearcher::with_mode(bytes, &key, search_mode)
+        .map_err(to_js_error)?;
+    Ok(CzdbSearcher { inner: searcher })
+}
+
 #[wasm_bindgen]
 impl CzdbSearcher {
     #[wasm_bindgen(constructor)]
-    pub fn new(data: &[u8], key: &str) -> Result<CzdbSearcher, JsError> {
-        let searcher = DbSearcher::new(data.to_vec(), key)
-            .map_err(|e| JsError::new(&e.to_string()))?;
+    pub fn new(data: Vec<u8>, key: &str) -> Result<CzdbSearcher, JsValue> {
+        let searcher = DbSearcher::new(data, key)
+            .map_err(to_js_error)?;
         Ok(CzdbSearcher { inner: searcher })
     }
 
-    /// Create with specific search mode (0=Memory, 1=BTree)
+    /// Create with specific search mode (0=Memory, 1=BTree, 2=Direct)
     #[wasm_bindgen]
-    pub fn new_with_mode(data: &[u8], key: &str, mode: u8) -> Result<CzdbSearcher, JsError> {
-        let search_mode = match mode {
-            0 => SearchMode::Memory,
-            1 => SearchMode::BTree,
-            _ => SearchMode::Memory,
-        };
-        let searcher = DbSearcher::with_mode(data.to_vec(), key, search_mode)
-            .map_err(|e| JsError::new(&e.to_string()))?;
+    pub fn new_with_mode(data: Vec<u8>, key: &str, mode: u8) -> Result<CzdbSearcher, JsValue> {
+        let search_mode = SearchMode::try_from(mode).map_err(to_js_error)?;
+        let searcher = DbSearcher::with_mode(data, key, search_mode)
+            .map_err(to_js_error)?;
         Ok(CzdbSearcher { inner: searcher })
     }
 
-    pub fn search(&self, ip: &str) -> Result<String, JsError> {
-        self.inner.search(ip).map_err(|e| JsError::new(&e.to_string()))
+    pub fn search(&self, ip: &str) -> Result<String, JsValue> {
+        self.inner.search(ip).map_err(to_js_error)
     }
 
     // Batch search to reduce WASM call overhead
-    pub fn search_batch(&self, ips: Vec<String>) -> Result<Vec<String>, JsError> {
+    // Failed lookups yield "Error"; use `searchBatchDetailed` to tell failures apart
+    pub fn search_batch(&self, ips: Vec<String>) -> Result<Vec<String>, JsValue> {
         let mut results = Vec::with_capacity(ips.len());
         for ip in ips {
             results.push(self.inner.search(&ip).unwrap_or_else(|_| "Error".to_string()));
@@ -44,11 +243,97 @@ impl CzdbSearcher {
         Ok(results)
     }
 
-    /// Get current search mode (0=Memory, 1=BTree)
+    /// Batch search reporting every failure with its index, error code and message
+    #[wasm_bindgen(js_name = searchBatchDetailed)]
+    pub fn search_batch_detailed(&self, ips: Vec<String>) -> CzdbBatchResultObject {
+        let results = Array::new_with_length(ips.len() as u32);
+        let errors = Array::new();
+
+        for (i, ip) in ips.iter().enumerate() {
+            match self.inner.search(ip) {
+                Ok(region) => results.set(i as u32, JsValue::from_str(&region)),
+                Err(e) => {
+                    results.set(i as u32, JsValue::NULL);
+                    let error = Object::new();
+                    Reflect::set(&error, &"index".into(), &JsValue::from(i as u32)).unwrap_throw();
+                    Reflect::set(&error, &"code".into(), &JsValue::from_str(e.code())).unwrap_throw();
+                    Reflect::set(&error, &"message".into(), &JsValue::from_str(&e.to_string())).unwrap_throw();
+                    errors.push(&error);
+                }
+            }
+        }
+
+        let result = Object::new();
+        Reflect::set(&result, &"results".into(), &results).unwrap_throw();
+        Reflect::set(&result, &"errors".into(), &errors).unwrap_throw();
+        result.unchecked_into()
+    }
+
+    /// Batch search IPv4 addresses given as unsigned integers (8.8.8.8 = 0x08080808)
+    #[wasm_bindgen(js_name = searchBatchU32)]
+    pub fn search_batch_u32(&self, ips: &[u32]) -> Result<CzdbRegionIdsObject, JsValue> {
+        let batch = self.inner.search_batch_v4(ips).map_err(to_js_error)?;
+        Ok(region_ids_to_js(batch))
+    }
+
+    /// Batch search IPv6 addresses packed as consecutive 16-byte (network order) chunks
+    #[wasm_bindgen(js_name = searchBatchV6)]
+    pub fn search_batch_v6(&self, ips: &[u8]) -> Result<CzdbRegionIdsObject, JsValue> {
+        if !ips.len().is_multiple_of(16) {
+            return Err(js_error("INVALID_INPUT", "IPv6 batch length must be a multiple of 16 bytes").into());
+        }
+        let ips: Vec<u128> = ips
+            .chunks_exact(16)
+            .map(|chunk| u128::from_be_bytes(chunk.try_into().unwrap()))
+            .collect();
+        let batch = self.inner.search_batch_v6(&ips).map_err(to_js_error)?;
+        Ok(region_ids_to_js(batch))
+    }
+
+    /// Search and return a `CzdbRecord`, or `undefined` when no range matches.
+    /// `language` overrides the searcher's language for this call.
+    #[wasm_bindgen(js_name = searchRecord)]
+    pub fn search_record(&self, ip: &str, language: Option<String>) -> Result<Option<CzdbRecordObject>, JsValue> {
+        let language = language_from_js(&self.inner, language)?;
+        let record = self.inner.search_record(ip).map_err(to_js_error)?;
+        Ok(record.map(|record| record_to_js(&record, language)))
+    }
+
+    /// Batch version of `searchRecord`; invalid addresses yield `undefined`,
+    /// other errors such as a corrupt database are thrown
+    #[wasm_bindgen(js_name = searchBatchRecords)]
+    pub fn search_batch_records(&self, ips: Vec<String>, language: Option<String>) -> Result<CzdbRecordArray, JsValue> {
+        let language = language_from_js(&self.inner, language)?;
+        let results = Array::new_with_length(ips.len() as u32);
+        for (i, ip) in ips.iter().enumerate() {
+            match self.inner.search_record(ip) {
+                Ok(Some(record)) => results.set(i as u32, record_to_js(&record, language).into()),
+                Ok(None) | Err(CzdbError::IpParseError(_) | CzdbError::InvalidIpType) => {}
+                Err(e) => return Err(to_js_error(e)),
+            }
+        }
+        Ok(results.unchecked_into())
+    }
+
+    /// Set the language of record names: `zh`, `zh-Hant`, `en` or `pinyin`
+    #[wasm_bindgen(js_name = setLanguage)]
+    pub fn set_language(&mut self, language: &str) -> Result<(), JsValue> {
+        self.inner.set_language(language.parse().map_err(to_js_error)?);
+        Ok(())
+    }
+
+    /// Language of record names as a tag: `zh-Hans`, `zh-Hant`, `en` or `zh-Latn`
+    #[wasm_bindgen(getter)]
+    pub fn language(&self) -> String {
+        self.inner.language().to_string()
+    }
+
+    /// Get current search mode (0=Memory, 1=BTree, 2=Direct)
     pub fn search_mode(&self) -> u8 {
         match self.inner.search_mode() {
             SearchMode::Memory => 0,
             SearchMode::BTree => 1,
+            SearchMode::Direct => 2,
         }
     }
 }
@@ -0,0 +1,191 @@
+//! Native Node.js addon (napi-rs)
+//!
+//! Exposes the same `CzdbSearcher` surface as the WASM build, plus
+//! `searchBatchAsync` which runs lookups on the libuv threadpool.
+
+use crate::searcher::{CzdbError, DbSearcher, Language, Location, RegionRecord, SearchMode};
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use std::sync::Arc;
+
+/// Errors carry the stable `CzdbError::code` as the JS `code` property
+fn to_napi_error(e: CzdbError) -> Error<&'static str> {
+    Error::new(e.code(), e.to_string())
+}
+
+fn search_all(searcher: &DbSearcher, ips: &[String]) -> Vec<String> {
+    ips.iter()
+        .map(|ip| searcher.search(ip).unwrap_or_else(|_| "Error".to_string()))
+        .collect()
+}
+
+/// Structured search result, the same shape as the WASM `CzdbRecord`;
+/// missing levels and codes are empty strings
+#[napi(object)]
+pub struct CzdbRecord {
+    pub country: String,
+    pub province: String,
+    pub city: String,
+    pub isp: String,
+    pub start: String,
+    pub end: String,
+    pub raw: String,
+    pub country_code: String,
+    pub country_alpha3: String,
+    pub subdivision_code: String,
+    pub carrier: String,
+    pub isp_category: String,
+}
+
+/// Record with names in the given language, as the WASM `searchRecord` builds it
+impl From<(RegionRecord, Language)> for CzdbRecord {
+    fn from((record, language): (RegionRecord, Language)) -> Self {
+        let location = Location::parse(&record.region).localized(language);
+        let fields = location.record_fields();
+        CzdbRecord {
+            country: fields.country.to_string(),
+            province: fields.province.to_string(),
+            city: fields.city.to_string(),
+            isp: fields.isp.to_string(),
+            start: record.start.to_string(),
+            end: record.end.to_string(),
+            country_code: fields.country_code.to_string(),
+            country_alpha3: fields.country_alpha3.to_string(),
+            subdivision_code: fields.subdivision_code.to_string(),
+            carrier: fields.carrier.to_string(),
+            isp_category: fields.isp_category.to_string(),
+            raw: record.region,
+        }
+    }
+}
+
+#[napi(js_name = "CzdbSearcher")]
+pub struct NodeSearcher {
+    inner: Arc<DbSearcher>,
+}
+
+#[napi]
+impl NodeSearcher {
+    #[napi(constructor)]
+    pub fn new(data: Buffer, key: String) -> Result<Self, &'static str> {
+        let searcher = DbSearcher::new(data.to_vec(), &key).map_err(to_napi_error)?;
+        Ok(NodeSearcher { inner: Arc::new(searcher) })
+    }
+
+    /// Create with specific search mode (0=Memory, 1=BTree, 2=Direct)
+    #[napi(factory, js_name = "newWithMode")]
+    pub fn new_with_mode(data: Buffer, key: String, mode: u8) -> Result<Self, &'static str> {
+        let search_mode = SearchMode::try_from(mode).map_err(to_napi_error)?;
+        let searcher = DbSearcher::with_mode(data.to_vec(), &key, search_mode)
+            .map_err(to_napi_error)?;
+        Ok(NodeSearcher { inner: Arc::new(searcher) })
+    }
+
+    #[napi]
+    pub fn search(&self, ip: String) -> Result<String, &'static str> {
+        self.inner.search(&ip).map_err(to_napi_error)
+    }
+
+    /// Search and return a `CzdbRecord`, or `undefined` when no range matches.
+    /// `language` overrides the searcher's language for this call.
+    #[napi(js_name = "searchRecord")]
+    pub fn search_record(&self, ip: String, language: Option<String>) -> Result<Option<CzdbRecord>, &'static str> {
+        let language = match language {
+            Some(tag) => tag.parse().map_err(to_napi_error)?,
+            None => self.inner.language(),
+        };
+        let record = self.inner.search_record(&ip).map_err(to_napi_error)?;
+        Ok(record.map(|record| CzdbRecord::from((record, language))))
+    }
+
+    #[napi(js_name = "searchBatch")]
+    pub fn search_batch(&self, ips: Vec<String>) -> Vec<String> {
+        search_all(&self.inner, &ips)
+    }
+
+    /// Batch search on the libuv threadpool, resolves to the same array as `searchBatch`
+    #[napi(js_name = "searchBatchAsync", ts_return_type = "Promise<string[]>")]
+    pub fn search_batch_async(&self, ips: Vec<String>) -> AsyncTask<SearchBatchTask> {
+        AsyncTask::new(SearchBatchTask {
+            searcher: Arc::clone(&self.inner),
+            ips,
+        })
+    }
+
+    /// Get current search mode (0=Memory, 1=BTree, 2=Direct)
+    #[napi(js_name = "searchMode")]
+    pub fn search_mode(&self) -> u8 {
+        match self.inner.search_mode() {
+            SearchMode::Memory => 0,
+            SearchMode::BTree => 1,
+            SearchMode::Direct => 2,
+        }
+    }
+}
+
+pub struct SearchBatchTask {
+    searcher: Arc<DbSearcher>,
+    ips: Vec<String>,
+}
+
+impl Task for SearchBatchTask {
+    type Output = Vec<String>;
+    type JsValue = Vec<String>;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        Ok(search_all(&self.searcher, &self.ips))
+    }
+
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::IpAddr;
+
+    fn record(region: &str) -> RegionRecord {
+        RegionRecord {
+            start: "101.226.0.0".parse::<IpAddr>().unwrap(),
+            end: "101.226.255.255".parse::<IpAddr>().unwrap(),
+            region: region.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_record_from_region() {
+        let zh = CzdbRecord::from((record("中国–上海–上海\t电信"), Language::Chinese));
+        assert_eq!((zh.country.as_str(), zh.province.as_str(), zh.city.as_str()), ("中国", "上海", "上海"));
+        assert_eq!((zh.isp.as_str(), zh.carrier.as_str(), zh.isp_category.as_str()), ("电信", "chinatelecom", "residential"));
+        assert_eq!((zh.country_code.as_str(), zh.country_alpha3.as_str(), zh.subdivision_code.as_str()), ("CN", "CHN", "CN-SH"));
+        assert_eq!((zh.start.as_str(), zh.end.as_str()), ("101.226.0.0", "101.226.255.255"));
+        assert_eq!(zh.raw, "中国–上海–上海\t电信");
+
+        let en = CzdbRecord::from((record("中国–上海–上海\t电信"), Language::English));
+        assert_eq!((en.country.as_str(), en.province.as_str(), en.city.as_str()), ("China", "Shanghai", "Shanghai"));
+        assert_eq!(en.raw, zh.raw);
+
+        // Missing levels and codes are empty
+        let area = CzdbRecord::from((record("亚太地区"), Language::Chinese));
+        assert_eq!((area.country.as_str(), area.province.as_str(), area.isp.as_str()), ("亚太地区", "", ""));
+        assert_eq!((area.country_code.as_str(), area.carrier.as_str()), ("", ""));
+    }
+
+    #[test]
+    fn test_error_codes() {
+        for (e, code) in [
+            (CzdbError::InvalidIpType, "IP_TYPE_MISMATCH"),
+            (CzdbError::WrongKey, "WRONG_KEY"),
+            (CzdbError::InvalidOption("mode".to_string()), "INVALID_OPTION"),
+        ] {
+            let message = e.to_string();
+            let error = to_napi_error(e);
+            assert_eq!(error.status, code);
+            assert_eq!(error.reason, message);
+        }
+        let e = "not an ip".parse::<IpAddr>().unwrap_err();
+        assert_eq!(to_napi_error(e.into()).status, "INVALID_IP");
+    }
+}
@@ -4,34 +4,194 @@
 //! - Memory: Full memory load with optimized binary search
 //! - BTree: Hierarchical index, file streaming (no full load)
 
-use crate::decrypt::{decrypt_aes_ecb, decrypt_xor};
-use byteorder::{ByteOrder, LE};
-use std::net::IpAddr;
+use crate::decrypt::{decrypt_aes_ecb, decrypt_xor, DecryptError};
+pub use crate::key::{CzdbKey, IntoCzdbKey};
+use byteorder::{ByteOrder, BE, LE};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::str::FromStr;
+use std::sync::OnceLock;
 use thiserror::Error;
 use std::io::Cursor;
 
+mod cache;
+mod dictionary;
+mod direct;
+mod division;
+mod export;
+mod intern;
+mod iso;
+mod isp;
+mod locale;
+mod prefix;
+mod region;
+mod reverse;
+mod text;
+mod verify;
+use cache::ResultCache;
+pub use cache::CacheStats;
+pub use dictionary::{GeoRow, RegionDictionary, RegionString};
+pub use direct::DirectTable;
+pub use division::AdminDivision;
+pub use export::{ListFormat, ListOptions};
+use intern::RegionTable;
+pub use iso::Country;
+pub use isp::{Carrier, IspCategory};
+pub use locale::Language;
+pub use prefix::RangeOverlap;
+pub use region::{DivisionKind, Location};
+use reverse::ReverseIndex;
+pub use reverse::{RegionFilter, RegionMatch, RegionRange};
+pub use text::TextEncoding;
+pub use verify::{VerifyCheck, VerifyIssue, VerifyReport};
+
+/// Database structure an error was detected in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    /// Plain header and AES-encrypted block at the start of the file
+    HyperHeader,
+    /// 17-byte super header (IP type, index pointers)
+    SuperHeader,
+    /// BTree header block (start IP + index pointer pairs)
+    BTreeHeader,
+    /// Index records
+    Index,
+    /// Column selection and XOR-encrypted geo map
+    GeoMap,
+    /// msgpack region record referenced by an index record
+    Region,
+    /// Saved Direct mode /24 table
+    DirectTable,
+}
+
+impl std::fmt::Display for Section {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Section::HyperHeader => "hyper header",
+            Section::SuperHeader => "super header",
+            Section::BTreeHeader => "btree header",
+            Section::Index => "index",
+            Section::GeoMap => "geo map",
+            Section::Region => "region data",
+            Section::DirectTable => "direct table",
+        })
+    }
+}
+
+/// Errors carry the failing section and absolute file offset where applicable
 #[derive(Error, Debug)]
 pub enum CzdbError {
-    #[error("IO error")]
+    #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
-    #[error("Decryption error")]
+    #[error("Decryption error: {0}")]
     DecryptError(#[from] crate::decrypt::DecryptError),
-    #[error("Invalid database format")]
-    InvalidFormat,
+    #[error("Truncated {section} at offset {offset}: need {needed} bytes, {available} available")]
+    Truncated {
+        section: Section,
+        offset: usize,
+        needed: usize,
+        available: usize,
+    },
+    #[error("Invalid {section} at offset {offset}: {reason}")]
+    InvalidFormat {
+        section: Section,
+        offset: usize,
+        reason: String,
+    },
+    #[error("Wrong key: the encrypted header does not decrypt with the given key")]
+    WrongKey,
     #[allow(dead_code)]
     #[error("Database expired")]
     Expired,
-    #[error("Client ID mismatch")]
-    ClientIdMismatch,
+    #[error("Client ID mismatch: file header has {expected}, encrypted block has {actual}")]
+    ClientIdMismatch { expected: u32, actual: u32 },
     #[error("IP parse error")]
     IpParseError(#[from] std::net::AddrParseError),
-    #[error("Msgpack decode error")]
-    MsgpackError(#[from] rmp::decode::ValueReadError),
+    #[error("Msgpack decode error in {section} at offset {offset}: {source}")]
+    MsgpackError {
+        section: Section,
+        offset: usize,
+        source: rmp::decode::ValueReadError,
+    },
     #[error("Invalid IP Type")]
     InvalidIpType,
     #[error("Invalid search mode")]
     InvalidSearchMode,
+    #[error("Invalid option: {0}")]
+    InvalidOption(String),
+    #[error("Invalid {encoding} text in {section} at offset {offset}")]
+    InvalidText {
+        section: Section,
+        offset: usize,
+        encoding: TextEncoding,
+    },
+}
+
+impl CzdbError {
+    /// Stable, machine-readable error code
+    pub fn code(&self) -> &'static str {
+        match self {
+            CzdbError::IoError(_) => "IO_ERROR",
+            CzdbError::DecryptError(_) => "DECRYPT_ERROR",
+            CzdbError::Truncated { .. } => "TRUNCATED",
+            CzdbError::InvalidFormat { .. } => "INVALID_FORMAT",
+            CzdbError::WrongKey => "WRONG_KEY",
+            CzdbError::Expired => "EXPIRED",
+            CzdbError::ClientIdMismatch { .. } => "CLIENT_ID_MISMATCH",
+            CzdbError::IpParseError(_) => "INVALID_IP",
+            CzdbError::MsgpackError { .. } => "CORRUPT_RECORD",
+            CzdbError::InvalidIpType => "IP_TYPE_MISMATCH",
+            CzdbError::InvalidSearchMode => "INVALID_SEARCH_MODE",
+            CzdbError::InvalidOption(_) => "INVALID_OPTION",
+            CzdbError::InvalidText { .. } => "INVALID_TEXT",
+        }
+    }
+
+    /// Section of the database file the error refers to, if any
+    pub fn section(&self) -> Option<Section> {
+        match self {
+            CzdbError::Truncated { section, .. }
+            | CzdbError::InvalidFormat { section, .. }
+            | CzdbError::MsgpackError { section, .. }
+            | CzdbError::InvalidText { section, .. } => Some(*section),
+            CzdbError::ClientIdMismatch { .. } | CzdbError::WrongKey => Some(Section::HyperHeader),
+            _ => None,
+        }
+    }
+
+    /// Absolute file offset the error refers to, if any
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            CzdbError::Truncated { offset, .. }
+            | CzdbError::InvalidFormat { offset, .. }
+            | CzdbError::MsgpackError { offset, .. }
+            | CzdbError::InvalidText { offset, .. } => Some(*offset),
+            _ => None,
+        }
+    }
+}
+
+/// Overflow-checked `base + rel` for offsets read from the file
+fn offset_add(section: Section, base: usize, rel: usize) -> Result<usize, CzdbError> {
+    base.checked_add(rel).ok_or(CzdbError::InvalidFormat {
+        section,
+        offset: base,
+        reason: format!("offset {} overflows", rel),
+    })
+}
+
+/// Bounds-checked `data[offset..offset + len]`
+fn slice_at(data: &[u8], section: Section, offset: usize, len: usize) -> Result<&[u8], CzdbError> {
+    offset
+        .checked_add(len)
+        .and_then(|end| data.get(offset..end))
+        .ok_or(CzdbError::Truncated {
+            section,
+            offset,
+            needed: len,
+            available: data.len().saturating_sub(offset),
+        })
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -47,12 +207,261 @@ pub enum SearchMode {
     Memory,
     /// Hierarchical index, file streaming (no full load)
     BTree,
+    /// IPv4 only: Memory mode plus a 64 MiB table with an entry per /24,
+    /// see [`DirectTable`]
+    Direct,
+}
+
+impl TryFrom<u8> for SearchMode {
+    type Error = CzdbError;
+
+    /// 0=Memory, 1=BTree, 2=Direct
+    fn try_from(mode: u8) -> Result<Self, Self::Error> {
+        match mode {
+            0 => Ok(SearchMode::Memory),
+            1 => Ok(SearchMode::BTree),
+            2 => Ok(SearchMode::Direct),
+            _ => Err(CzdbError::InvalidSearchMode),
+        }
+    }
+}
+
+/// In-memory index layout used by Memory mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IndexLayout {
+    /// Records in file order, binary searched over a separate array of start IPs
+    #[default]
+    Sorted,
+    /// Records in Eytzinger (BFS) order with start IP, end IP and data pointer
+    /// stored together, so each probe touches one cache line and the next
+    /// levels are predictable. The prefix jump table is not used.
+    Eytzinger,
+}
+
+/// Searcher configuration for [`DbSearcher::with_options`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchOptions {
+    pub mode: SearchMode,
+    /// Memory mode: number of leading address bits indexed by the prefix jump table,
+    /// which narrows each binary search to the records sharing that prefix.
+    /// 0 disables the table; at most [`SearchOptions::MAX_JUMP_BITS`].
+    pub jump_bits: u8,
+    /// Memory mode: index layout
+    pub layout: IndexLayout,
+    /// Decode every distinct region once at load time, so lookups no longer
+    /// decode msgpack and [`DbSearcher::search_shared`] does not allocate
+    pub intern_regions: bool,
+    /// Number of matched ranges kept in a thread-safe LRU result cache,
+    /// 0 disables the cache
+    pub cache_capacity: usize,
+    /// Encoding of the region strings
+    pub encoding: TextEncoding,
+    /// Fail with [`CzdbError::InvalidText`] on invalid byte sequences instead
+    /// of replacing them with U+FFFD
+    pub strict_encoding: bool,
+    /// Language of the place names returned by [`DbSearcher::search_location`]
+    pub language: Language,
+}
+
+impl SearchOptions {
+    /// Default jump table size: 2^16 + 1 entries (256 KiB)
+    pub const DEFAULT_JUMP_BITS: u8 = 16;
+    /// Largest jump table: 2^24 + 1 entries (64 MiB)
+    pub const MAX_JUMP_BITS: u8 = 24;
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        SearchOptions {
+            mode: SearchMode::Memory,
+            jump_bits: Self::DEFAULT_JUMP_BITS,
+            layout: IndexLayout::Sorted,
+            intern_regions: false,
+            cache_capacity: 0,
+            encoding: TextEncoding::Auto,
+            strict_encoding: false,
+            language: Language::Chinese,
+        }
+    }
+}
+
+/// A matched database range and its region string
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegionRecord {
+    pub start: IpAddr,
+    pub end: IpAddr,
+    pub region: String,
+}
+
+/// Result of a batch search: region ids plus the region strings they refer to
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BatchRegions {
+    /// Index into `regions` per address, [`BatchRegions::NO_MATCH`] or [`BatchRegions::CORRUPT`]
+    pub ids: Vec<i32>,
+    /// Distinct region strings
+    pub regions: Vec<String>,
+}
+
+impl BatchRegions {
+    /// No range covers the address
+    pub const NO_MATCH: i32 = -1;
+    /// The index or the region record of the matched range is corrupt
+    pub const CORRUPT: i32 = -2;
+}
+
+/// Deduplicates regions by data pointer first (skipping the decode), then by value
+struct BatchRegionsBuilder {
+    batch: BatchRegions,
+    by_ptr: HashMap<usize, i32>,
+    by_region: HashMap<String, i32>,
+}
+
+impl BatchRegionsBuilder {
+    fn with_capacity(capacity: usize) -> Self {
+        BatchRegionsBuilder {
+            batch: BatchRegions { ids: Vec::with_capacity(capacity), regions: Vec::new() },
+            by_ptr: HashMap::new(),
+            by_region: HashMap::new(),
+        }
+    }
+
+    fn push(&mut self, searcher: &DbSearcher, record: Result<Option<IndexRecord>, CzdbError>) {
+        let id = match record {
+            Err(_) => BatchRegions::CORRUPT,
+            Ok(None) => BatchRegions::NO_MATCH,
+            Ok(Some(record)) => match self.by_ptr.get(&record.data_ptr) {
+                Some(&id) => id,
+                None => {
+                    let id = match searcher.get_region(record.data_ptr, record.data_len) {
+                        Ok(region) => match self.by_region.get(&region) {
+                            Some(&id) => id,
+                            None => {
+                                let id = self.batch.regions.len() as i32;
+                                self.by_region.insert(region.clone(), id);
+                                self.batch.regions.push(region);
+                                id
+                            }
+                        },
+                        Err(_) => BatchRegions::CORRUPT,
+                    };
+                    self.by_ptr.insert(record.data_ptr, id);
+                    id
+                }
+            },
+        };
+        self.batch.ids.push(id);
+    }
+
+    fn finish(self) -> BatchRegions {
+        self.batch
+    }
+}
+
+/// Index record located by a search (IPv4 addresses are widened to u128)
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct IndexRecord {
+    start: u128,
+    end: u128,
+    data_ptr: usize,
+    data_len: usize,
 }
 
 /// Header block for BTree mode (16 bytes IP + 4 bytes pointer)
 const HEADER_BLOCK_LENGTH: usize = 20;
 const SUPER_PART_LENGTH: usize = 17;
 
+/// Memory mode index: raw index records plus the start IPs for binary search,
+/// or the same records in Eytzinger order
+#[derive(Debug, Clone, Default)]
+struct MemoryIndex {
+    data: Vec<u8>,              // Raw index bytes, including the last record
+    v4_keys: Vec<u32>,          // IPv4 start IPs for binary search
+    v6_keys: Vec<u128>,         // IPv6 start IPs for binary search
+    // Eytzinger layout: node k has children 2k and 2k + 1, node 0 is unused
+    // (when set, `data` and the key arrays are empty)
+    v4_tree: Vec<EytzingerNode<u32>>,
+    v6_tree: Vec<EytzingerNode<u128>>,
+    // Prefix jump table: jump[p]..jump[p + 1] are the records whose start IP has
+    // the leading `jump_bits` bits equal to p (empty when disabled)
+    jump: Vec<u32>,
+    jump_shift: u32,            // Address bits below the prefix
+}
+
+/// Index record with its key, as stored in the Eytzinger layout
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(C, align(16))]
+struct EytzingerNode<K> {
+    start: K,
+    end: K,
+    data_ptr: u32,
+    data_len: u8,
+}
+
+impl<K: Copy + Default + Ord + Into<u128>> EytzingerNode<K> {
+    /// Arrange nodes sorted by start IP in Eytzinger order
+    fn tree(sorted: Vec<Self>) -> Vec<Self> {
+        fn fill<K: Copy>(sorted: &[EytzingerNode<K>], tree: &mut [EytzingerNode<K>], next: &mut usize, k: usize) {
+            if k < tree.len() {
+                fill(sorted, tree, next, 2 * k);
+                tree[k] = sorted[*next];
+                *next += 1;
+                fill(sorted, tree, next, 2 * k + 1);
+            }
+        }
+
+        let mut tree = vec![Self::default(); sorted.len() + 1];
+        fill(&sorted, &mut tree, &mut 0, 1);
+        tree
+    }
+
+    /// The node with the greatest start IP at or before `ip`, if it covers `ip`
+    fn search(tree: &[Self], ip: K) -> Option<IndexRecord> {
+        // Going right means the node starts at or before ip; the last such node wins
+        let (mut k, mut best) = (1, 0);
+        while k < tree.len() {
+            let right = tree[k].start <= ip;
+            best = if right { k } else { best };
+            k = 2 * k + right as usize;
+        }
+
+        let node = tree.get(best).filter(|_| best != 0)?;
+        (ip <= node.end).then(|| IndexRecord {
+            start: node.start.into(),
+            end: node.end.into(),
+            data_ptr: node.data_ptr as usize,
+            data_len: node.data_len as usize,
+        })
+    }
+}
+
+impl MemoryIndex {
+    /// Build the jump table over sorted start IPs of `key_bits` (32 or 128) bits
+    fn jump_table<K: Copy + Into<u128>>(keys: &[K], bits: u8, key_bits: u32) -> (Vec<u32>, u32) {
+        let shift = key_bits - bits as u32;
+        let mut jump = Vec::with_capacity((1 << bits) + 1);
+        let mut idx = 0;
+        for prefix in 0..1u128 << bits {
+            let base = prefix << shift;
+            while idx < keys.len() && keys[idx].into() < base {
+                idx += 1;
+            }
+            jump.push(idx as u32);
+        }
+        jump.push(keys.len() as u32);
+        (jump, shift)
+    }
+
+    /// Slice of records that can start at or before `ip` within its prefix;
+    /// the record covering `ip` is the last of them, or the one just before
+    fn candidates(&self, ip: u128, len: usize) -> (usize, usize) {
+        if self.jump.is_empty() {
+            return (0, len);
+        }
+        let prefix = (ip >> self.jump_shift) as usize;
+        (self.jump[prefix] as usize, self.jump[prefix + 1] as usize)
+    }
+}
+
 /// BTree mode header index
 #[derive(Debug, Clone)]
 struct BTreeHeader {
@@ -65,42 +474,108 @@ pub struct DbSearcher {
     // Common fields
     data: Vec<u8>,              // Database data
     start_offset: usize,        // Data start offset
-    index_start_offset: usize,  // Index start offset
     ip_type: IpType,
     ip_bytes_len: usize,
     column_selection: u32,
     geo_map_data: Option<Vec<u8>>,
+    geo_map_offset: usize,      // File offset of the geo map, for error reporting
 
     // Mode-specific fields
     search_mode: SearchMode,
+    jump_bits: u8,              // Memory mode prefix jump table size, 0 = disabled
+    layout: IndexLayout,        // Memory mode index layout
 
-    // Memory mode: flat index arrays
+    // Memory and Direct mode: flat index arrays
     // Store raw index data for cache-friendly access
-    index_data: Vec<u8>,        // Raw index bytes
-    index_v4_keys: Vec<u32>,    // IPv4 start IPs for binary search
-    index_v6_keys: Vec<u128>,   // IPv6 start IPs for binary search
+    memory_index: Option<MemoryIndex>,
+    direct_table: Option<DirectTable>,  // Direct mode /24 table
+    region_table: Option<RegionTable>,  // Interned regions, see `SearchOptions::intern_regions`
+    cache: Option<ResultCache>,         // Result cache, see `SearchOptions::cache_capacity`
+    encoding: TextEncoding,             // Region text encoding, never `Auto` once loaded
+    encoding_option: TextEncoding,      // Encoding as given in the options, kept for `options()`
+    strict_encoding: bool,
+    language: Language,                 // Place names of `search_location`
+    reverse_index: OnceLock<ReverseIndex>,  // Built by the first reverse lookup, see `search_region`
     record_len: usize,          // Length of each index record
 
     // BTree mode: hierarchical index
     btree_header: Option<BTreeHeader>,
-    end_index_ptr: usize,
+    start_index_ptr: usize,     // First index record, relative to start_offset
+    end_index_ptr: usize,       // Last index record (inclusive), relative to start_offset
 }
 
 impl DbSearcher {
     /// Create a new searcher with default mode (Memory)
-    pub fn new(data: Vec<u8>, key: &str) -> Result<Self, CzdbError> {
+    pub fn new(data: Vec<u8>, key: impl IntoCzdbKey) -> Result<Self, CzdbError> {
         Self::with_mode(data, key, SearchMode::Memory)
     }
 
+    /// Create a searcher with the first of `keys` that decrypts the database
+    ///
+    /// Meant for key rotation: pass the new key first and the old one after it.
+    /// Fails with [`CzdbError::WrongKey`] when none of the keys match.
+    pub fn with_candidate_keys<K: IntoCzdbKey + Clone>(data: Vec<u8>, keys: &[K], mode: SearchMode) -> Result<Self, CzdbError> {
+        let idx = Self::matching_key(&data, keys)?;
+        Self::with_mode(data, keys[idx].clone(), mode)
+    }
+
+    /// Index of the first of `keys` that decrypts the database header
+    ///
+    /// Keys that are wrong or malformed are skipped; errors caused by the file
+    /// itself (truncation, invalid format) are returned immediately. When no key
+    /// matches, the most telling failure is returned: a client id mismatch (the
+    /// header decrypted to a plausible block whose random size fits in the
+    /// file), then [`CzdbError::WrongKey`], then a malformed key.
+    pub fn matching_key<K: IntoCzdbKey + Clone>(data: &[u8], keys: &[K]) -> Result<usize, CzdbError> {
+        let rank = |e: &CzdbError| match e {
+            CzdbError::ClientIdMismatch { .. } => 2,
+            CzdbError::WrongKey => 1,
+            _ => 0,
+        };
+        let mut best = CzdbError::WrongKey;
+        for (idx, key) in keys.iter().enumerate() {
+            match key.clone().into_czdb_key().and_then(|key| Self::parse_header(data, &key)) {
+                Ok(_) => return Ok(idx),
+                Err(e @ (CzdbError::WrongKey | CzdbError::ClientIdMismatch { .. } | CzdbError::DecryptError(_))) => {
+                    if idx == 0 || rank(&e) > rank(&best) {
+                        best = e;
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Err(best)
+    }
+
     /// Create a searcher with specific mode
-    pub fn with_mode(data: Vec<u8>, key: &str, mode: SearchMode) -> Result<Self, CzdbError> {
-        let (_header_block, offset) = Self::parse_header(&data, key)?;
+    pub fn with_mode(data: Vec<u8>, key: impl IntoCzdbKey, mode: SearchMode) -> Result<Self, CzdbError> {
+        Self::with_options(data, key, SearchOptions { mode, ..SearchOptions::default() })
+    }
 
-        // Read Super Header (17 bytes) at offset
-        if data.len() < offset + SUPER_PART_LENGTH {
-            return Err(CzdbError::InvalidFormat);
+    /// Create a searcher with explicit options
+    pub fn with_options(data: Vec<u8>, key: impl IntoCzdbKey, options: SearchOptions) -> Result<Self, CzdbError> {
+        Self::open(data, key, options, None)
+    }
+
+    fn open(
+        data: Vec<u8>,
+        key: impl IntoCzdbKey,
+        options: SearchOptions,
+        direct_table: Option<DirectTable>,
+    ) -> Result<Self, CzdbError> {
+        if options.jump_bits > SearchOptions::MAX_JUMP_BITS {
+            return Err(CzdbError::InvalidOption(format!(
+                "jump_bits {} exceeds {}",
+                options.jump_bits,
+                SearchOptions::MAX_JUMP_BITS
+            )));
         }
-        let super_header = &data[offset..offset + SUPER_PART_LENGTH];
+        let mode = options.mode;
+        let key = key.into_czdb_key()?;
+        let (_header_block, offset) = Self::parse_header(&data, &key)?;
+
+        // Read Super Header (17 bytes) at offset
+        let super_header = slice_at(&data, Section::SuperHeader, offset, SUPER_PART_LENGTH)?;
 
         let ip_type = if super_header[0] & 1 == 0 { IpType::Ipv4 } else { IpType::Ipv6 };
         let header_block_size = LE::read_u32(&super_header[5..9]) as usize;
@@ -110,56 +585,93 @@ impl DbSearcher {
         let record_len = if ip_type == IpType::Ipv4 { 13 } else { 37 };
 
         let mut searcher = DbSearcher {
-            data: data.clone(),
+            data: Vec::new(),
             start_offset: offset,
-            index_start_offset: offset + start_index_ptr,
             ip_type,
             ip_bytes_len,
             column_selection: 0,
             geo_map_data: None,
+            geo_map_offset: 0,
             search_mode: mode,
-            index_data: Vec::new(),
-            index_v4_keys: Vec::new(),
-            index_v6_keys: Vec::new(),
+            jump_bits: options.jump_bits,
+            // Direct mode reads records by position, which needs the sorted layout
+            layout: if mode == SearchMode::Direct { IndexLayout::Sorted } else { options.layout },
+            memory_index: None,
+            direct_table: None,
+            region_table: None,
+            cache: (options.cache_capacity > 0).then(|| ResultCache::new(options.cache_capacity)),
+            encoding: options.encoding,
+            encoding_option: options.encoding,
+            strict_encoding: options.strict_encoding,
+            language: options.language,
+            reverse_index: OnceLock::new(),
             record_len,
             btree_header: None,
+            start_index_ptr,
             end_index_ptr,
         };
 
         // Load geo mapping first (needed by all modes)
-        searcher.load_geo_mapping(key, &data)?;
+        searcher.load_geo_mapping(&key, &data)?;
 
         // Build index based on mode
         match mode {
             SearchMode::Memory => {
-                searcher.build_memory_index(start_index_ptr, end_index_ptr, &data)?;
+                searcher.memory_index = Some(searcher.build_memory_index(&data)?);
             }
             SearchMode::BTree => {
-                searcher.build_btree_index(start_index_ptr, end_index_ptr, &data)?;
+                searcher.btree_header = Some(searcher.build_btree_index(&data)?);
+            }
+            SearchMode::Direct => {
+                let index = searcher.build_memory_index(&data)?;
+                searcher.direct_table = Some(searcher.build_direct_table(&index, direct_table)?);
+                searcher.memory_index = Some(index);
             }
         }
 
+        // Take ownership of the buffer only once parsing is done (no extra copy)
+        searcher.data = data;
+
+        // Detected once the region records are readable
+        if searcher.encoding == TextEncoding::Auto {
+            searcher.encoding = searcher.detect_encoding();
+        }
+
+        if options.intern_regions {
+            let records = searcher.index_bytes()?.chunks_exact(record_len).map(|r| searcher.parse_record(r));
+            let table = searcher.build_region_table(records);
+            searcher.region_table = Some(table);
+        }
+
         Ok(searcher)
     }
 
-    fn parse_header(data: &[u8], key: &str) -> Result<(HyperHeaderBlock, usize), CzdbError> {
-        if data.len() < 12 {
-            return Err(CzdbError::InvalidFormat);
-        }
+    fn parse_header(data: &[u8], key: &CzdbKey) -> Result<(HyperHeaderBlock, usize), CzdbError> {
+        let fixed = slice_at(data, Section::HyperHeader, 0, 12)?;
 
-        let version = LE::read_u32(&data[0..4]);
-        let client_id = LE::read_u32(&data[4..8]);
-        let encrypted_block_size = LE::read_u32(&data[8..12]) as usize;
+        let version = LE::read_u32(&fixed[0..4]);
+        let client_id = LE::read_u32(&fixed[4..8]);
+        let encrypted_block_size = LE::read_u32(&fixed[8..12]) as usize;
 
-        if data.len() < 12 + encrypted_block_size {
-            return Err(CzdbError::InvalidFormat);
+        let encrypted_bytes = slice_at(data, Section::HyperHeader, 12, encrypted_block_size)?;
+        if encrypted_block_size == 0 || !encrypted_block_size.is_multiple_of(16) {
+            return Err(CzdbError::InvalidFormat {
+                section: Section::HyperHeader,
+                offset: 8,
+                reason: format!("encrypted block size {} is not a positive multiple of 16", encrypted_block_size),
+            });
         }
-
-        let encrypted_bytes = &data[12..12 + encrypted_block_size];
-        let decrypted_bytes = decrypt_aes_ecb(key, encrypted_bytes)?;
+        let decrypted_bytes = decrypt_aes_ecb(key, encrypted_bytes).map_err(|e| match e {
+            DecryptError::InvalidPadding => CzdbError::WrongKey,
+            e => e.into(),
+        })?;
 
         if decrypted_bytes.len() < 8 {
-            return Err(CzdbError::InvalidFormat);
+            return Err(CzdbError::InvalidFormat {
+                section: Section::HyperHeader,
+                offset: 12,
+                reason: format!("decrypted block is {} bytes, expected at least 8", decrypted_bytes.len()),
+            });
         }
 
         let first_u32 = LE::read_u32(&decrypted_bytes[0..4]);
@@ -168,7 +680,15 @@ impl DbSearcher {
         let random_size = LE::read_u32(&decrypted_bytes[4..8]) as usize;
 
         if decrypted_client_id != client_id {
-            return Err(CzdbError::ClientIdMismatch);
+            // A wrong key still leaves valid padding about once in 256 tries; its
+            // block is noise, with a random size pointing far past the file
+            if (12 + encrypted_block_size).saturating_add(random_size) > data.len() {
+                return Err(CzdbError::WrongKey);
+            }
+            return Err(CzdbError::ClientIdMismatch {
+                expected: client_id,
+                actual: decrypted_client_id,
+            });
         }
 
         let header_block = HyperHeaderBlock {
@@ -182,350 +702,370 @@ impl DbSearcher {
             },
         };
 
-        let offset = 12 + encrypted_block_size + random_size;
+        let offset = offset_add(Section::HyperHeader, 12 + encrypted_block_size, random_size)?;
         Ok((header_block, offset))
     }
 
-    fn load_geo_mapping(&mut self, key: &str, data: &[u8]) -> Result<(), CzdbError> {
-        let column_selection_ptr = self.start_offset + self.end_index_ptr + self.ip_bytes_len * 2 + 5;
-
-        if data.len() < column_selection_ptr + 4 {
-            return Ok(());
-        }
+    fn load_geo_mapping(&mut self, key: &CzdbKey, data: &[u8]) -> Result<(), CzdbError> {
+        let column_selection_ptr = offset_add(Section::GeoMap, self.start_offset, self.end_index_ptr)?
+            .saturating_add(self.record_len);
 
-        self.column_selection = LE::read_u32(&data[column_selection_ptr..column_selection_ptr + 4]);
+        let column_selection = match data.get(column_selection_ptr..column_selection_ptr.saturating_add(4)) {
+            Some(bytes) => LE::read_u32(bytes),
+            None => return Ok(()),
+        };
+        self.column_selection = column_selection;
 
         if self.column_selection == 0 {
             return Ok(());
         }
 
         let geo_map_ptr = column_selection_ptr + 4;
-        if data.len() < geo_map_ptr + 4 {
-            return Err(CzdbError::InvalidFormat);
-        }
-
-        let geo_map_size = LE::read_u32(&data[geo_map_ptr..geo_map_ptr + 4]) as usize;
+        let geo_map_size = LE::read_u32(slice_at(data, Section::GeoMap, geo_map_ptr, 4)?) as usize;
         let geo_map_data_ptr = geo_map_ptr + 4;
 
-        if data.len() < geo_map_data_ptr + geo_map_size {
-            return Err(CzdbError::InvalidFormat);
-        }
-
-        let mut geo_map_data = data[geo_map_data_ptr..geo_map_data_ptr + geo_map_size].to_vec();
-        decrypt_xor(key, &mut geo_map_data)?;
+        let mut geo_map_data = slice_at(data, Section::GeoMap, geo_map_data_ptr, geo_map_size)?.to_vec();
+        decrypt_xor(key, &mut geo_map_data);
 
         self.geo_map_data = Some(geo_map_data);
+        self.geo_map_offset = geo_map_data_ptr;
 
         Ok(())
     }
 
     /// Build memory index with cache-friendly layout
-    fn build_memory_index(&mut self, start_ptr: usize, end_ptr: usize, data: &[u8]) -> Result<(), CzdbError> {
-        let start_offset = self.start_offset + start_ptr;
-        let end_offset = self.start_offset + end_ptr;
-
+    fn build_memory_index(&self, data: &[u8]) -> Result<MemoryIndex, CzdbError> {
+        let (start_ptr, end_ptr) = (self.start_index_ptr, self.end_index_ptr);
+        let start_offset = offset_add(Section::Index, self.start_offset, start_ptr)?;
+        // end_ptr points at the last record, which is part of the index
+        let end_offset = offset_add(Section::Index, self.start_offset, end_ptr)?.saturating_add(self.record_len);
+
+        if end_ptr < start_ptr {
+            return Err(CzdbError::InvalidFormat {
+                section: Section::SuperHeader,
+                offset: self.start_offset + 13,
+                reason: format!("end index pointer {} precedes start index pointer {}", end_ptr, start_ptr),
+            });
+        }
         if end_offset > data.len() {
-            return Err(CzdbError::InvalidFormat);
+            return Err(CzdbError::Truncated {
+                section: Section::Index,
+                offset: start_offset,
+                needed: end_offset.saturating_sub(start_offset),
+                available: data.len().saturating_sub(start_offset),
+            });
         }
 
         // Copy raw index data for fast access
-        self.index_data = data[start_offset..end_offset].to_vec();
+        let mut index = MemoryIndex {
+            data: slice_at(data, Section::Index, start_offset, end_offset - start_offset)?.to_vec(),
+            ..MemoryIndex::default()
+        };
 
-        let count = (end_ptr - start_ptr) / self.record_len + 1;
+        if self.layout == IndexLayout::Eytzinger {
+            let records = std::mem::take(&mut index.data);
+            let records = records.chunks_exact(self.record_len).map(|r| self.parse_record(r));
+            match self.ip_type {
+                IpType::Ipv4 => index.v4_tree = EytzingerNode::tree(records.map(|r| EytzingerNode {
+                    start: r.start as u32,
+                    end: r.end as u32,
+                    data_ptr: r.data_ptr as u32,
+                    data_len: r.data_len as u8,
+                }).collect()),
+                IpType::Ipv6 => index.v6_tree = EytzingerNode::tree(records.map(|r| EytzingerNode {
+                    start: r.start,
+                    end: r.end,
+                    data_ptr: r.data_ptr as u32,
+                    data_len: r.data_len as u8,
+                }).collect()),
+            }
+            return Ok(index);
+        }
 
+        let records = index.data.chunks_exact(self.record_len);
         match self.ip_type {
-            IpType::Ipv4 => {
-                self.index_v4_keys.reserve(count);
-                let mut ptr = 0;
-                while ptr + self.record_len <= self.index_data.len() {
-                    let start_ip = u32::from_be_bytes(self.index_data[ptr..ptr+4].try_into().unwrap());
-                    self.index_v4_keys.push(start_ip);
-                    ptr += self.record_len;
-                }
-            }
-            IpType::Ipv6 => {
-                self.index_v6_keys.reserve(count);
-                let mut ptr = 0;
-                while ptr + self.record_len <= self.index_data.len() {
-                    let start_ip = u128::from_be_bytes(self.index_data[ptr..ptr+16].try_into().unwrap());
-                    self.index_v6_keys.push(start_ip);
-                    ptr += self.record_len;
-                }
-            }
+            IpType::Ipv4 => index.v4_keys = records.map(|r| BE::read_u32(&r[0..4])).collect(),
+            IpType::Ipv6 => index.v6_keys = records.map(|r| BE::read_u128(&r[0..16])).collect(),
         }
-        Ok(())
+        if self.jump_bits > 0 {
+            (index.jump, index.jump_shift) = match self.ip_type {
+                IpType::Ipv4 => MemoryIndex::jump_table(&index.v4_keys, self.jump_bits, 32),
+                IpType::Ipv6 => MemoryIndex::jump_table(&index.v6_keys, self.jump_bits, 128),
+            };
+        }
+        Ok(index)
     }
 
     /// Build BTree hierarchical index
-    fn build_btree_index(&mut self, start_ptr: usize, end_ptr: usize, data: &[u8]) -> Result<(), CzdbError> {
+    fn build_btree_index(&self, data: &[u8]) -> Result<BTreeHeader, CzdbError> {
         // Read total header block size from super header at position 9
-        let total_header_block_size = LE::read_u32(&data[self.start_offset + 9..self.start_offset + 13]) as usize;
+        let total_header_block_size = LE::read_u32(slice_at(data, Section::SuperHeader, self.start_offset + 9, 4)?) as usize;
+        if !total_header_block_size.is_multiple_of(HEADER_BLOCK_LENGTH) {
+            return Err(CzdbError::InvalidFormat {
+                section: Section::SuperHeader,
+                offset: self.start_offset + 9,
+                reason: format!(
+                    "header block size {} is not a multiple of {}",
+                    total_header_block_size, HEADER_BLOCK_LENGTH
+                ),
+            });
+        }
 
         // Read the header block data
         let header_data_offset = self.start_offset + SUPER_PART_LENGTH;
-        if header_data_offset + total_header_block_size > data.len() {
-            return Err(CzdbError::InvalidFormat);
-        }
-
-        let header_data = &data[header_data_offset..header_data_offset + total_header_block_size];
+        let header_data = slice_at(data, Section::BTreeHeader, header_data_offset, total_header_block_size)?;
 
         let len = total_header_block_size / HEADER_BLOCK_LENGTH;
         let mut header_sip: Vec<Vec<u8>> = Vec::with_capacity(len);
         let mut header_ptr: Vec<usize> = Vec::with_capacity(len);
 
-        let mut ptr = 0;
-        while ptr < total_header_block_size {
-            let data_ptr = LE::read_u32(&header_data[ptr + 16..ptr + 20]) as usize;
+        for block in header_data.chunks_exact(HEADER_BLOCK_LENGTH) {
+            let data_ptr = LE::read_u32(&block[16..20]) as usize;
             if data_ptr == 0 {
                 break;
             }
 
-            let sip = header_data[ptr..ptr + 16].to_vec();
-            header_sip.push(sip);
+            header_sip.push(block[0..16].to_vec());
             header_ptr.push(data_ptr);
-            ptr += HEADER_BLOCK_LENGTH;
         }
 
-        self.btree_header = Some(BTreeHeader {
+        Ok(BTreeHeader {
             header_sip,
             header_ptr,
-        });
-
-        Ok(())
+        })
     }
 
     /// Main search interface - dispatches to appropriate mode
     pub fn search(&self, ip: &str) -> Result<String, CzdbError> {
         let ip_addr = IpAddr::from_str(ip)?;
+        if let Some(cache) = &self.cache {
+            let range = self.cached_locate(cache, ip_addr)?;
+            return Ok(range.map_or_else(|| "Unknown".to_string(), |r| r.region.to_string()));
+        }
 
-        match (self.ip_type, ip_addr) {
-            (IpType::Ipv4, IpAddr::V4(addr)) => self.search_ipv4(addr.octets()),
-            (IpType::Ipv6, IpAddr::V6(addr)) => self.search_ipv6(addr.octets()),
-            _ => Err(CzdbError::InvalidIpType),
+        match self.locate(ip_addr)? {
+            Some(record) => self.get_region(record.data_ptr, record.data_len),
+            None => Ok("Unknown".to_string()),
         }
     }
 
-    /// IPv4 search dispatcher
-    fn search_ipv4(&self, ip: [u8; 4]) -> Result<String, CzdbError> {
-        let ip_u32 = u32::from_be_bytes(ip);
+    /// Search and return the matched range together with its region,
+    /// or `None` when no range covers the address
+    pub fn search_record(&self, ip: &str) -> Result<Option<RegionRecord>, CzdbError> {
+        let ip_addr = IpAddr::from_str(ip)?;
+        if let Some(cache) = &self.cache {
+            return Ok(self.cached_locate(cache, ip_addr)?.map(|r| RegionRecord {
+                start: self.to_ip_addr(r.start),
+                end: self.to_ip_addr(r.end),
+                region: r.region.to_string(),
+            }));
+        }
 
-        match self.search_mode {
-            SearchMode::Memory => self.memory_search_ipv4(ip_u32),
-            SearchMode::BTree => self.btree_search_ipv4(ip),
+        match self.locate(ip_addr)? {
+            Some(record) => Ok(Some(RegionRecord {
+                start: self.to_ip_addr(record.start),
+                end: self.to_ip_addr(record.end),
+                region: self.get_region(record.data_ptr, record.data_len)?,
+            })),
+            None => Ok(None),
         }
     }
 
-    /// IPv6 search dispatcher
-    fn search_ipv6(&self, ip: [u8; 16]) -> Result<String, CzdbError> {
-        let ip_u128 = u128::from_be_bytes(ip);
-
-        match self.search_mode {
-            SearchMode::Memory => self.memory_search_ipv6(ip_u128),
-            SearchMode::BTree => self.btree_search_ipv6(ip),
+    /// Batch search IPv4 addresses given as integers (8.8.8.8 = 0x08080808)
+    ///
+    /// Returns one region id per address indexing into a deduplicated region table,
+    /// so each distinct region is decoded and allocated only once.
+    pub fn search_batch_v4(&self, ips: &[u32]) -> Result<BatchRegions, CzdbError> {
+        if self.ip_type != IpType::Ipv4 {
+            return Err(CzdbError::InvalidIpType);
+        }
+        let mut batch = BatchRegionsBuilder::with_capacity(ips.len());
+        for &ip in ips {
+            batch.push(self, self.locate_ipv4(ip));
         }
+        Ok(batch.finish())
     }
 
-    /// Memory mode: Standard binary search with cached index data
-    fn memory_search_ipv4(&self, ip: u32) -> Result<String, CzdbError> {
-        let idx = match self.index_v4_keys.binary_search(&ip) {
-            Ok(i) => i,
-            Err(i) => if i > 0 { i - 1 } else { return Ok("Unknown".to_string()) },
-        };
-
-        // Read record directly from cached index data
-        let offset = idx * self.record_len;
-
-        let end_ip = u32::from_be_bytes(self.index_data[offset+4..offset+8].try_into().unwrap());
-
-        if ip <= end_ip {
-            let data_ptr = LE::read_u32(&self.index_data[offset+8..offset+12]);
-            let data_len = self.index_data[offset+12];
-            return self.get_region(data_ptr as usize, data_len as usize);
+    /// Batch search IPv6 addresses given as integers, see [`DbSearcher::search_batch_v4`]
+    pub fn search_batch_v6(&self, ips: &[u128]) -> Result<BatchRegions, CzdbError> {
+        if self.ip_type != IpType::Ipv6 {
+            return Err(CzdbError::InvalidIpType);
         }
-
-        Ok("Unknown".to_string())
+        let mut batch = BatchRegionsBuilder::with_capacity(ips.len());
+        for &ip in ips {
+            batch.push(self, self.locate_ipv6(ip));
+        }
+        Ok(batch.finish())
     }
 
-    fn memory_search_ipv6(&self, ip: u128) -> Result<String, CzdbError> {
-        let idx = match self.index_v6_keys.binary_search(&ip) {
-            Ok(i) => i,
-            Err(i) => if i > 0 { i - 1 } else { return Ok("Unknown".to_string()) },
-        };
-
-        let offset = idx * self.record_len;
-
-        let end_ip = u128::from_be_bytes(self.index_data[offset+16..offset+32].try_into().unwrap());
-
-        if ip <= end_ip {
-            let data_ptr = LE::read_u32(&self.index_data[offset+32..offset+36]);
-            let data_len = self.index_data[offset+36];
-            return self.get_region(data_ptr as usize, data_len as usize);
+    /// Find the index record covering `ip`
+    fn locate(&self, ip: IpAddr) -> Result<Option<IndexRecord>, CzdbError> {
+        match (self.ip_type, ip) {
+            (IpType::Ipv4, IpAddr::V4(addr)) => self.locate_ipv4(u32::from(addr)),
+            (IpType::Ipv6, IpAddr::V6(addr)) => self.locate_ipv6(u128::from(addr)),
+            _ => Err(CzdbError::InvalidIpType),
         }
-
-        Ok("Unknown".to_string())
     }
 
-    /// BTree mode: Hierarchical index search
-    fn btree_search_ipv4(&self, ip: [u8; 4]) -> Result<String, CzdbError> {
-        let header = self.btree_header.as_ref().ok_or(CzdbError::InvalidSearchMode)?;
-
-        // Binary search on header
-        let mut l = 0i32;
-        let mut h = (header.header_sip.len() as i32) - 1;
-        let mut sptr = 0usize;
-        let mut eptr = 0usize;
-
-        while l <= h {
-            let m = (l + h) / 2;
-            let cmp = Self::compare_ip_bytes(&ip, &header.header_sip[m as usize], 4);
-
-            if cmp < 0 {
-                h = m - 1;
-            } else if cmp > 0 {
-                l = m + 1;
-            } else {
-                sptr = header.header_ptr[if m > 0 { m as usize - 1 } else { 0 }];
-                eptr = header.header_ptr[m as usize];
-                break;
+    /// IPv4 search dispatcher
+    fn locate_ipv4(&self, ip: u32) -> Result<Option<IndexRecord>, CzdbError> {
+        match (self.search_mode, &self.memory_index, &self.btree_header) {
+            (SearchMode::Memory, Some(index), _) => Ok(self.memory_search_ipv4(index, ip)),
+            (SearchMode::Direct, Some(index), _) => {
+                Ok(self.direct_table.as_ref().and_then(|table| self.direct_search_ipv4(table, index, ip)))
             }
+            (SearchMode::BTree, _, Some(header)) => self.btree_search(header, &ip.to_be_bytes(), ip as u128),
+            _ => Ok(None),
         }
+    }
 
-        if l > h {
-            if l == 0 {
-                return Ok("Unknown".to_string());
-            }
-            if (l as usize) < header.header_sip.len() {
-                sptr = header.header_ptr[l as usize - 1];
-                eptr = header.header_ptr[l as usize];
-            } else if h >= 0 && (h as usize) + 1 < header.header_sip.len() {
-                sptr = header.header_ptr[h as usize];
-                eptr = header.header_ptr[h as usize + 1];
-            } else {
-                sptr = header.header_ptr[header.header_sip.len() - 1];
-                eptr = sptr + self.record_len;
-            }
+    /// IPv6 search dispatcher
+    fn locate_ipv6(&self, ip: u128) -> Result<Option<IndexRecord>, CzdbError> {
+        match (self.search_mode, &self.memory_index, &self.btree_header) {
+            (SearchMode::Memory, Some(index), _) => Ok(self.memory_search_ipv6(index, ip)),
+            (SearchMode::BTree, _, Some(header)) => self.btree_search(header, &ip.to_be_bytes(), ip),
+            _ => Ok(None),
         }
+    }
 
-        if sptr == 0 {
-            return Ok("Unknown".to_string());
+    fn to_ip_addr(&self, ip: u128) -> IpAddr {
+        match self.ip_type {
+            IpType::Ipv4 => IpAddr::V4(Ipv4Addr::from(ip as u32)),
+            IpType::Ipv6 => IpAddr::V6(Ipv6Addr::from(ip)),
         }
+    }
 
-        // Read index block directly from data (no extra allocation)
-        let block_len = eptr - sptr;
-        let data_offset = self.start_offset + sptr;
-
-        // Binary search in block
-        let mut l = 0i32;
-        let mut h = (block_len / self.record_len) as i32 - 1;
-        let mut data_ptr = 0u32;
-        let mut data_len = 0u8;
-
-        while l <= h {
-            let m = (l + h) / 2;
-            let p = m as usize * self.record_len;
-            let slice_offset = data_offset + p;
+    /// Decode one index record; `bytes` must be exactly `record_len` long
+    fn parse_record(&self, bytes: &[u8]) -> IndexRecord {
+        let ip_len = self.ip_bytes_len;
+        let (start, end) = match self.ip_type {
+            IpType::Ipv4 => (BE::read_u32(&bytes[0..4]) as u128, BE::read_u32(&bytes[4..8]) as u128),
+            IpType::Ipv6 => (BE::read_u128(&bytes[0..16]), BE::read_u128(&bytes[16..32])),
+        };
+        IndexRecord {
+            start,
+            end,
+            data_ptr: LE::read_u32(&bytes[ip_len * 2..ip_len * 2 + 4]) as usize,
+            data_len: bytes[ip_len * 2 + 4] as usize,
+        }
+    }
 
-            let start_ip = u32::from_be_bytes(self.data[slice_offset..slice_offset+4].try_into().unwrap());
-            let end_ip = u32::from_be_bytes(self.data[slice_offset+4..slice_offset+8].try_into().unwrap());
+    /// Decode the index record at `position` of the index, rejecting a range
+    /// that ends before it starts
+    fn checked_record(&self, bytes: &[u8], position: usize) -> Result<IndexRecord, CzdbError> {
+        let record = self.parse_record(bytes);
+        if record.end < record.start {
+            return Err(CzdbError::InvalidFormat {
+                section: Section::Index,
+                offset: self.start_offset + self.start_index_ptr + position * self.record_len,
+                reason: format!(
+                    "range ends at {} before it starts at {}",
+                    self.to_ip_addr(record.end),
+                    self.to_ip_addr(record.start)
+                ),
+            });
+        }
+        Ok(record)
+    }
 
-            let ip_u32 = u32::from_be_bytes(ip);
+    /// Read the index record at `idx` from the cached index data
+    fn memory_record(&self, index: &MemoryIndex, idx: usize) -> Option<IndexRecord> {
+        let offset = idx.checked_mul(self.record_len)?;
+        let bytes = index.data.get(offset..offset + self.record_len)?;
+        Some(self.parse_record(bytes))
+    }
 
-            if ip_u32 >= start_ip && ip_u32 <= end_ip {
-                data_ptr = LE::read_u32(&self.data[slice_offset+8..slice_offset+12]);
-                data_len = self.data[slice_offset+12];
-                break;
-            } else if ip_u32 < start_ip {
-                h = m - 1;
-            } else {
-                l = m + 1;
-            }
+    /// Memory mode: Standard binary search with cached index data
+    fn memory_search_ipv4(&self, index: &MemoryIndex, ip: u32) -> Option<IndexRecord> {
+        if self.layout == IndexLayout::Eytzinger {
+            return EytzingerNode::search(&index.v4_tree, ip);
         }
 
-        if data_ptr == 0 {
-            return Ok("Unknown".to_string());
+        // Last record starting at or before ip, searched within the prefix's slice
+        let (lo, hi) = index.candidates(ip as u128, index.v4_keys.len());
+        let idx = (lo + index.v4_keys[lo..hi].partition_point(|&k| k <= ip)).checked_sub(1)?;
+
+        // Read record directly from cached index data
+        let record = self.memory_record(index, idx)?;
+        if ip as u128 <= record.end {
+            return Some(record);
         }
 
-        self.get_region(data_ptr as usize, data_len as usize)
+        None
     }
 
-    fn btree_search_ipv6(&self, ip: [u8; 16]) -> Result<String, CzdbError> {
-        let header = self.btree_header.as_ref().ok_or(CzdbError::InvalidSearchMode)?;
-
-        let mut l = 0i32;
-        let mut h = (header.header_sip.len() as i32) - 1;
-        let mut sptr = 0usize;
-        let mut eptr = 0usize;
+    fn memory_search_ipv6(&self, index: &MemoryIndex, ip: u128) -> Option<IndexRecord> {
+        if self.layout == IndexLayout::Eytzinger {
+            return EytzingerNode::search(&index.v6_tree, ip);
+        }
 
-        while l <= h {
-            let m = (l + h) / 2;
-            let cmp = Self::compare_ip_bytes(&ip, &header.header_sip[m as usize], 16);
+        let (lo, hi) = index.candidates(ip, index.v6_keys.len());
+        let idx = (lo + index.v6_keys[lo..hi].partition_point(|&k| k <= ip)).checked_sub(1)?;
 
-            if cmp < 0 {
-                h = m - 1;
-            } else if cmp > 0 {
-                l = m + 1;
-            } else {
-                sptr = header.header_ptr[if m > 0 { m as usize - 1 } else { 0 }];
-                eptr = header.header_ptr[m as usize];
-                break;
-            }
+        let record = self.memory_record(index, idx)?;
+        if ip <= record.end {
+            return Some(record);
         }
 
-        if l > h {
-            if l == 0 {
-                return Ok("Unknown".to_string());
-            }
-            if (l as usize) < header.header_sip.len() {
-                sptr = header.header_ptr[l as usize - 1];
-                eptr = header.header_ptr[l as usize];
-            } else if h >= 0 && (h as usize) + 1 < header.header_sip.len() {
-                sptr = header.header_ptr[h as usize];
-                eptr = header.header_ptr[h as usize + 1];
-            } else {
-                sptr = header.header_ptr[header.header_sip.len() - 1];
-                eptr = sptr + self.record_len;
-            }
+        None
+    }
+
+    /// BTree mode: Hierarchical index search
+    ///
+    /// `ip_bytes` is the big-endian address (4 or 16 bytes), `ip` the same value as an integer.
+    /// Header and index pointers come straight from the file, so every read is bounds-checked.
+    fn btree_search(&self, header: &BTreeHeader, ip_bytes: &[u8], ip: u128) -> Result<Option<IndexRecord>, CzdbError> {
+        let ip_len = ip_bytes.len();
+
+        // Binary search on header: the last block starting at or before the address
+        let idx = header
+            .header_sip
+            .partition_point(|sip| Self::compare_ip_bytes(ip_bytes, sip, ip_len) >= 0);
+        if idx == 0 {
+            return Ok(None);
         }
 
+        // The block runs up to and including the record the next header entry points at
+        let sptr = header.header_ptr[idx - 1];
+        let eptr = header.header_ptr.get(idx).copied().unwrap_or(self.end_index_ptr).min(self.end_index_ptr);
+
         if sptr == 0 {
-            return Ok("Unknown".to_string());
+            return Ok(None);
         }
 
         // Read index block directly from data (no extra allocation)
-        let block_len = eptr - sptr;
-        let data_offset = self.start_offset + sptr;
+        let block_len = eptr.checked_sub(sptr).ok_or_else(|| CzdbError::InvalidFormat {
+            section: Section::BTreeHeader,
+            offset: self.start_offset + SUPER_PART_LENGTH + (idx - 1) * HEADER_BLOCK_LENGTH,
+            reason: format!("index pointer {} lies beyond {}", sptr, eptr),
+        })?;
+        let data_offset = offset_add(Section::Index, self.start_offset, sptr)?;
+        let block_len = (block_len - block_len % self.record_len).saturating_add(self.record_len);
+        let block = slice_at(&self.data, Section::Index, data_offset, block_len)?;
+        let records = block.len() / self.record_len;
 
-        let mut l = 0i32;
-        let mut h = (block_len / self.record_len) as i32 - 1;
-        let mut data_ptr = 0u32;
-        let mut data_len = 0u8;
-
-        while l <= h {
-            let m = (l + h) / 2;
-            let p = m as usize * self.record_len;
-            let slice_offset = data_offset + p;
-
-            let start_ip = u128::from_be_bytes(self.data[slice_offset..slice_offset+16].try_into().unwrap());
-            let end_ip = u128::from_be_bytes(self.data[slice_offset+16..slice_offset+32].try_into().unwrap());
+        // Binary search in block
+        let mut l = 0usize;
+        let mut h = records;
 
-            let ip_u128 = u128::from_be_bytes(ip);
+        while l < h {
+            let m = l + (h - l) / 2;
+            let p = m * self.record_len;
+            let record = self.parse_record(&block[p..p + self.record_len]);
 
-            if ip_u128 >= start_ip && ip_u128 <= end_ip {
-                data_ptr = LE::read_u32(&self.data[slice_offset+32..slice_offset+36]);
-                data_len = self.data[slice_offset+36];
-                break;
-            } else if ip_u128 < start_ip {
-                h = m - 1;
+            if ip >= record.start && ip <= record.end {
+                if record.data_ptr == 0 {
+                    return Ok(None);
+                }
+                return Ok(Some(record));
+            } else if ip < record.start {
+                h = m;
             } else {
                 l = m + 1;
             }
         }
 
-        if data_ptr == 0 {
-            return Ok("Unknown".to_string());
-        }
-
-        self.get_region(data_ptr as usize, data_len as usize)
+        Ok(None)
     }
 
     /// Compare two IP byte arrays
@@ -542,13 +1082,26 @@ impl DbSearcher {
 
     /// Get region data by pointer and length
     fn get_region(&self, ptr: usize, len: usize) -> Result<String, CzdbError> {
-        let offset = self.start_offset + ptr;
+        let mut result = String::with_capacity(64);
+        self.write_region(ptr, len, &mut result)?;
+        Ok(result)
+    }
 
-        if offset + len > self.data.len() {
-            return Err(CzdbError::InvalidFormat);
+    /// Append the region to `result`, from the interned regions when available
+    fn write_region(&self, ptr: usize, len: usize, result: &mut String) -> Result<(), CzdbError> {
+        match self.region_table.as_ref().and_then(|table| table.get(ptr, len)) {
+            Some(region) => {
+                result.push_str(region);
+                Ok(())
+            }
+            None => self.decode_region(ptr, len, result),
         }
+    }
 
-        let region_bytes = &self.data[offset..offset+len];
+    /// Decode the msgpack region record at `ptr` and append it to `result`
+    fn decode_region(&self, ptr: usize, len: usize, result: &mut String) -> Result<(), CzdbError> {
+        let offset = offset_add(Section::Region, self.start_offset, ptr)?;
+        let region_bytes = slice_at(&self.data, Section::Region, offset, len)?;
         let mut buf = Cursor::new(region_bytes);
 
         let geo_pos_mix_size = rmp::decode::read_int(&mut buf).unwrap_or(0) as u64;
@@ -556,58 +1109,82 @@ impl DbSearcher {
         let geo_len = ((geo_pos_mix_size >> 24) & 0xFF) as usize;
         let geo_ptr = (geo_pos_mix_size & 0x00FFFFFF) as usize;
 
-        let mut result = String::with_capacity(64);
-
         if geo_pos_mix_size != 0 {
             if let Some(geo_map_data) = &self.geo_map_data {
-                self.append_geo_string(geo_map_data, geo_ptr, geo_len, &mut result)?;
+                self.append_geo_string(geo_map_data, geo_ptr, geo_len, self.strict_encoding, result)?;
             }
         }
 
-        match rmp::decode::read_str_len(&mut buf) {
-            Ok(str_len) => {
-                let str_len = str_len as usize;
-                let pos = buf.position() as usize;
-                if pos + str_len <= region_bytes.len() {
-                    let str_bytes = &region_bytes[pos..pos+str_len];
-                    result.push_str(&String::from_utf8_lossy(str_bytes));
-                }
+        if let Ok(str_len) = rmp::decode::read_str_len(&mut buf) {
+            let pos = buf.position() as usize;
+            if let Some(str_bytes) = region_bytes.get(pos..).and_then(|rest| rest.get(..str_len as usize)) {
+                result.push_str(&self.decode_text(str_bytes, self.strict_encoding, Section::Region, offset + pos)?);
             }
-            Err(_) => {}
         }
 
-        Ok(result)
+        Ok(())
     }
 
-    fn append_geo_string(&self, geo_map_data: &[u8], ptr: usize, len: usize, result: &mut String) -> Result<(), CzdbError> {
-        if ptr + len > geo_map_data.len() {
-            return Err(CzdbError::InvalidFormat);
-        }
+    /// Decode region text in the database encoding; `offset` locates errors
+    fn decode_text<'a>(&self, bytes: &'a [u8], strict: bool, section: Section, offset: usize) -> Result<Cow<'a, str>, CzdbError> {
+        self.encoding.decode(bytes, strict).ok_or(CzdbError::InvalidText {
+            section,
+            offset,
+            encoding: self.encoding,
+        })
+    }
 
-        let data_row = &geo_map_data[ptr..ptr+len];
+    fn append_geo_string(
+        &self,
+        geo_map_data: &[u8],
+        ptr: usize,
+        len: usize,
+        strict: bool,
+        result: &mut String,
+    ) -> Result<(), CzdbError> {
+        let row_offset = self.geo_map_offset.saturating_add(ptr);
+        let data_row = geo_map_data
+            .get(ptr..ptr.saturating_add(len))
+            .ok_or(CzdbError::Truncated {
+                section: Section::GeoMap,
+                offset: row_offset,
+                needed: len,
+                available: geo_map_data.len().saturating_sub(ptr),
+            })?;
         let mut buf = Cursor::new(data_row);
+        let msgpack_err = |buf: &Cursor<&[u8]>, source| CzdbError::MsgpackError {
+            section: Section::GeoMap,
+            offset: row_offset.saturating_add(buf.position() as usize),
+            source,
+        };
 
-        let len = rmp::decode::read_array_len(&mut buf)?;
+        let len = rmp::decode::read_array_len(&mut buf).map_err(|e| msgpack_err(&buf, e))?;
 
         let mut first = true;
 
         for i in 0..len {
-            let column_selected = (self.column_selection >> (i + 1) & 1) == 1;
+            // Only the first 31 columns can be selected
+            let column_selected = self.column_selection.checked_shr(i + 1).is_some_and(|bits| bits & 1 == 1);
 
-            let str_len = rmp::decode::read_str_len(&mut buf)?;
+            let str_len = rmp::decode::read_str_len(&mut buf).map_err(|e| msgpack_err(&buf, e))?;
             let str_len = str_len as usize;
             let pos = buf.position() as usize;
 
-            if pos + str_len > data_row.len() {
-                return Err(CzdbError::InvalidFormat);
-            }
+            let str_bytes = data_row
+                .get(pos..)
+                .and_then(|rest| rest.get(..str_len))
+                .ok_or(CzdbError::Truncated {
+                    section: Section::GeoMap,
+                    offset: row_offset.saturating_add(pos),
+                    needed: str_len,
+                    available: data_row.len().saturating_sub(pos),
+                })?;
 
             if column_selected {
                 if !first {
                     result.push('\t');
                 }
-                let str_bytes = &data_row[pos..pos+str_len];
-                result.push_str(&String::from_utf8_lossy(str_bytes));
+                result.push_str(&self.decode_text(str_bytes, strict, Section::GeoMap, row_offset.saturating_add(pos))?);
                 first = false;
             }
 
@@ -621,6 +1198,67 @@ impl DbSearcher {
     pub fn search_mode(&self) -> SearchMode {
         self.search_mode
     }
+
+    /// Options this searcher was created with
+    pub fn options(&self) -> SearchOptions {
+        SearchOptions {
+            mode: self.search_mode,
+            jump_bits: self.jump_bits,
+            layout: self.layout,
+            intern_regions: self.region_table.is_some(),
+            cache_capacity: self.cache.as_ref().map_or(0, ResultCache::capacity),
+            encoding: self.encoding_option,
+            strict_encoding: self.strict_encoding,
+            language: self.language,
+        }
+    }
+
+    /// Change the language of [`DbSearcher::search_location`]; lookups and
+    /// the result cache are unaffected
+    pub fn set_language(&mut self, language: Language) {
+        self.language = language;
+    }
+
+    /// Replace the database, keeping this searcher's options
+    ///
+    /// The result cache and its counters start over, so no result from the
+    /// previous database is served. On error the current database stays loaded.
+    pub fn reload(&mut self, data: Vec<u8>, key: impl IntoCzdbKey) -> Result<(), CzdbError> {
+        *self = Self::with_options(data, key, self.options())?;
+        Ok(())
+    }
+
+    /// Encoding of the region strings, as given in the options or detected
+    pub fn encoding(&self) -> TextEncoding {
+        self.encoding
+    }
+
+    /// Language of the place names returned by [`DbSearcher::search_location`]
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
+    /// IP family of the database
+    pub fn ip_type(&self) -> IpType {
+        self.ip_type
+    }
+
+    /// Raw bytes of every index record, read from the file
+    fn index_bytes(&self) -> Result<&[u8], CzdbError> {
+        let start = offset_add(Section::Index, self.start_offset, self.start_index_ptr)?;
+        let len = self
+            .end_index_ptr
+            .checked_sub(self.start_index_ptr)
+            .ok_or(CzdbError::InvalidFormat {
+                section: Section::SuperHeader,
+                offset: self.start_offset + 13,
+                reason: format!(
+                    "end index pointer {} precedes start index pointer {}",
+                    self.end_index_ptr, self.start_index_ptr
+                ),
+            })?;
+        slice_at(&self.data, Section::Index, start, len.saturating_add(self.record_len))
+    }
 }
 
 #[allow(dead_code)]
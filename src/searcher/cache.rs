@@ -0,0 +1,159 @@
+//! Bounded LRU cache of lookup results
+//!
+//! Entries are keyed by the matched range rather than by address, so one entry
+//! answers every address of a range. A lookup finds the cached range with the
+//! greatest start at or before the address; addresses no range covers are not
+//! cached.
+
+use super::{CzdbError, DbSearcher, IpType};
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Snapshot of the result cache counters, see [`DbSearcher::cache_stats`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Ranges currently cached
+    pub entries: usize,
+    pub capacity: usize,
+}
+
+/// A cached range with its region
+#[derive(Debug, Clone)]
+pub(super) struct CachedRange {
+    pub(super) start: u128,
+    pub(super) end: u128,
+    pub(super) region: Arc<str>,
+}
+
+#[derive(Debug, Default)]
+struct LruState {
+    /// Cached ranges by start IP, with the tick of their last use
+    ranges: BTreeMap<u128, (CachedRange, u64)>,
+    /// Start IP of each range by last use, oldest first
+    order: BTreeMap<u64, u128>,
+    tick: u64,
+}
+
+#[derive(Debug)]
+pub(super) struct ResultCache {
+    capacity: usize,
+    state: Mutex<LruState>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl ResultCache {
+    pub(super) fn new(capacity: usize) -> Self {
+        ResultCache {
+            capacity,
+            state: Mutex::new(LruState::default()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// The state is consistent after every operation, so a poisoned lock is still usable
+    fn lock(&self) -> MutexGuard<'_, LruState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn get(&self, ip: u128) -> Option<CachedRange> {
+        let mut guard = self.lock();
+        let state = &mut *guard;
+        state.tick += 1;
+        let tick = state.tick;
+
+        let (_, (range, used)) = state.ranges.range_mut(..=ip).next_back()?;
+        if ip > range.end {
+            return None;
+        }
+        state.order.remove(used);
+        state.order.insert(tick, range.start);
+        *used = tick;
+        Some(range.clone())
+    }
+
+    fn insert(&self, range: CachedRange) {
+        let mut guard = self.lock();
+        let state = &mut *guard;
+        state.tick += 1;
+        let tick = state.tick;
+
+        let start = range.start;
+        if let Some((_, used)) = state.ranges.insert(start, (range, tick)) {
+            state.order.remove(&used);
+        }
+        state.order.insert(tick, start);
+
+        while state.ranges.len() > self.capacity {
+            match state.order.pop_first() {
+                Some((_, oldest)) => state.ranges.remove(&oldest),
+                None => break,
+            };
+        }
+    }
+
+    pub(super) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn clear(&self) {
+        *self.lock() = LruState::default();
+        self.hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+    }
+
+    fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.lock().ranges.len(),
+            capacity: self.capacity,
+        }
+    }
+}
+
+impl DbSearcher {
+    /// Result cache counters, or `None` when the cache is disabled
+    /// (see [`SearchOptions::cache_capacity`](super::SearchOptions::cache_capacity))
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.cache.as_ref().map(ResultCache::stats)
+    }
+
+    /// Drop every cached result and reset the counters
+    pub fn clear_cache(&self) {
+        if let Some(cache) = &self.cache {
+            cache.clear();
+        }
+    }
+
+    /// Look `ip` up through the result cache, which must be enabled
+    pub(super) fn cached_locate(&self, cache: &ResultCache, ip: IpAddr) -> Result<Option<CachedRange>, CzdbError> {
+        let key = match (self.ip_type, ip) {
+            (IpType::Ipv4, IpAddr::V4(addr)) => u32::from(addr) as u128,
+            (IpType::Ipv6, IpAddr::V6(addr)) => u128::from(addr),
+            _ => return Err(CzdbError::InvalidIpType),
+        };
+        if let Some(range) = cache.get(key) {
+            cache.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(Some(range));
+        }
+        cache.misses.fetch_add(1, Ordering::Relaxed);
+
+        let record = match self.locate(ip)? {
+            Some(record) => record,
+            None => return Ok(None),
+        };
+        let region: Arc<str> = match self.region_table.as_ref().and_then(|t| t.get(record.data_ptr, record.data_len)) {
+            Some(region) => Arc::clone(region),
+            None => self.get_region(record.data_ptr, record.data_len)?.into(),
+        };
+        let range = CachedRange { start: record.start, end: record.end, region };
+        cache.insert(range.clone());
+        Ok(Some(range))
+    }
+}
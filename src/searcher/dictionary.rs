@@ -0,0 +1,190 @@
+//! Region dictionary
+//!
+//! Region records pair a pointer into the geo map with a trailing string. The
+//! geo map is a list of msgpack rows; [`DbSearcher::region_dictionary`] lists
+//! every row and every distinct trailing string with the ranges and addresses
+//! that refer to them.
+
+use super::{offset_add, slice_at, CzdbError, DbSearcher, Section};
+use std::collections::HashMap;
+use std::io::Cursor;
+
+/// A row of the geo map
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeoRow {
+    /// Position of the row in the geo map, counting from 0
+    pub id: u32,
+    /// Every column of the row
+    pub columns: Vec<String>,
+    /// Selected columns joined by tabs, as they appear in region text
+    pub text: String,
+    /// Number of index ranges referring to the row
+    pub ranges: usize,
+    /// Number of addresses those ranges cover, saturating at `u128::MAX`
+    pub addresses: u128,
+}
+
+/// A distinct trailing string of the region records
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegionString {
+    /// Order of first appearance in the index, counting from 0
+    pub id: u32,
+    pub text: String,
+    /// Number of index ranges ending in this string
+    pub ranges: usize,
+    /// Number of addresses those ranges cover, saturating at `u128::MAX`
+    pub addresses: u128,
+}
+
+/// Every geo row and distinct trailing string of a database
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RegionDictionary {
+    /// Geo map rows in file order, including rows no range refers to
+    pub geo_rows: Vec<GeoRow>,
+    pub strings: Vec<RegionString>,
+}
+
+impl DbSearcher {
+    /// List the geo rows and trailing strings with their range and address counts
+    ///
+    /// Reads the whole index and decodes every region record once, so it
+    /// costs about as much as building a Memory mode searcher. Text is
+    /// decoded like lookups do, honouring
+    /// [`SearchOptions::strict_encoding`](super::SearchOptions::strict_encoding).
+    pub fn region_dictionary(&self) -> Result<RegionDictionary, CzdbError> {
+        let mut dictionary = RegionDictionary { geo_rows: self.geo_rows()?, strings: Vec::new() };
+        let row_ids: HashMap<usize, usize> = self.geo_row_offsets().into_iter().enumerate().map(|(id, ptr)| (ptr, id)).collect();
+
+        let mut string_ids: HashMap<String, usize> = HashMap::new();
+        // (geo row, string) per region record, which many ranges share
+        let mut parts: HashMap<usize, (Option<usize>, usize)> = HashMap::new();
+        for (position, bytes) in self.index_bytes()?.chunks_exact(self.record_len).enumerate() {
+            let record = self.checked_record(bytes, position)?;
+            let (row, string) = match parts.get(&record.data_ptr) {
+                Some(&parts) => parts,
+                None => {
+                    let (geo_ptr, text) = self.region_parts(record.data_ptr, record.data_len)?;
+                    let row = match geo_ptr {
+                        Some(ptr) => Some(*row_ids.get(&ptr).ok_or_else(|| CzdbError::InvalidFormat {
+                            section: Section::Region,
+                            offset: self.start_offset + record.data_ptr,
+                            reason: format!("geo pointer {} is not the start of a geo row", ptr),
+                        })?),
+                        None => None,
+                    };
+                    let next = dictionary.strings.len();
+                    let string = *string_ids.entry(text.clone()).or_insert_with(|| {
+                        dictionary.strings.push(RegionString { id: next as u32, text, ranges: 0, addresses: 0 });
+                        next
+                    });
+                    parts.insert(record.data_ptr, (row, string));
+                    (row, string)
+                }
+            };
+
+            let addresses = (record.end - record.start).saturating_add(1);
+            if let Some(row) = row {
+                let row = &mut dictionary.geo_rows[row];
+                row.ranges += 1;
+                row.addresses = row.addresses.saturating_add(addresses);
+            }
+            let string = &mut dictionary.strings[string];
+            string.ranges += 1;
+            string.addresses = string.addresses.saturating_add(addresses);
+        }
+
+        Ok(dictionary)
+    }
+
+    /// Decode every geo row, with no counts yet
+    fn geo_rows(&self) -> Result<Vec<GeoRow>, CzdbError> {
+        let Some(geo_map_data) = &self.geo_map_data else {
+            return Ok(Vec::new());
+        };
+        let mut rows = Vec::new();
+        let offsets = self.geo_row_offsets();
+        for (id, &ptr) in offsets.iter().enumerate() {
+            let end = offsets.get(id + 1).copied().unwrap_or(geo_map_data.len());
+            let mut text = String::new();
+            self.append_geo_string(geo_map_data, ptr, end - ptr, self.strict_encoding, &mut text)?;
+            let columns = self.geo_columns(geo_map_data, ptr)?;
+            rows.push(GeoRow { id: id as u32, columns, text, ranges: 0, addresses: 0 });
+        }
+        Ok(rows)
+    }
+
+    /// Start of every well-formed geo row, up to the first malformed one
+    fn geo_row_offsets(&self) -> Vec<usize> {
+        let Some(geo_map_data) = &self.geo_map_data else {
+            return Vec::new();
+        };
+        let mut offsets = Vec::new();
+        let mut buf = Cursor::new(geo_map_data.as_slice());
+        'rows: while (buf.position() as usize) < geo_map_data.len() {
+            let ptr = buf.position() as usize;
+            let Ok(columns) = rmp::decode::read_array_len(&mut buf) else {
+                break;
+            };
+            for _ in 0..columns {
+                let end = rmp::decode::read_str_len(&mut buf)
+                    .ok()
+                    .and_then(|len| (buf.position() as usize).checked_add(len as usize))
+                    .filter(|&end| end <= geo_map_data.len());
+                match end {
+                    Some(end) => buf.set_position(end as u64),
+                    None => break 'rows,
+                }
+            }
+            offsets.push(ptr);
+        }
+        offsets
+    }
+
+    /// Every column of the geo row at `ptr`, selected or not
+    fn geo_columns(&self, geo_map_data: &[u8], ptr: usize) -> Result<Vec<String>, CzdbError> {
+        // Offsets of truncation errors are made absolute, like those of the other geo map errors
+        let geo_slice = |pos: usize, len: usize| {
+            slice_at(geo_map_data, Section::GeoMap, pos, len).map_err(|e| match e {
+                CzdbError::Truncated { section, offset, needed, available } => {
+                    CzdbError::Truncated { section, offset: self.geo_map_offset + offset, needed, available }
+                }
+                e => e,
+            })
+        };
+        let mut buf = Cursor::new(geo_slice(ptr, geo_map_data.len().saturating_sub(ptr))?);
+        let msgpack_err = |buf: &Cursor<&[u8]>, source| CzdbError::MsgpackError {
+            section: Section::GeoMap,
+            offset: self.geo_map_offset + ptr + buf.position() as usize,
+            source,
+        };
+        let count = rmp::decode::read_array_len(&mut buf).map_err(|e| msgpack_err(&buf, e))?;
+        let mut columns = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let len = rmp::decode::read_str_len(&mut buf).map_err(|e| msgpack_err(&buf, e))? as usize;
+            let pos = ptr + buf.position() as usize;
+            let text = self.decode_text(geo_slice(pos, len)?, self.strict_encoding, Section::GeoMap, self.geo_map_offset + pos)?;
+            columns.push(text.into_owned());
+            buf.set_position(buf.position() + len as u64);
+        }
+        Ok(columns)
+    }
+
+    /// Geo map pointer and trailing string of the region record at `ptr`
+    fn region_parts(&self, ptr: usize, len: usize) -> Result<(Option<usize>, String), CzdbError> {
+        let offset = offset_add(Section::Region, self.start_offset, ptr)?;
+        let region_bytes = slice_at(&self.data, Section::Region, offset, len)?;
+        let mut buf = Cursor::new(region_bytes);
+
+        let geo_pos_mix_size = rmp::decode::read_int(&mut buf).unwrap_or(0u64);
+        let geo_ptr = (geo_pos_mix_size != 0 && self.geo_map_data.is_some()).then_some((geo_pos_mix_size & 0x00FFFFFF) as usize);
+
+        let mut text = String::new();
+        if let Ok(str_len) = rmp::decode::read_str_len(&mut buf) {
+            let pos = buf.position() as usize;
+            if let Some(str_bytes) = region_bytes.get(pos..).and_then(|rest| rest.get(..str_len as usize)) {
+                text = self.decode_text(str_bytes, self.strict_encoding, Section::Region, offset + pos)?.into_owned();
+            }
+        }
+        Ok((geo_ptr, text))
+    }
+}
@@ -0,0 +1,167 @@
+//! Direct mode: one table entry per IPv4 /24
+//!
+//! Each of the 2^24 entries holds the index of the record covering the whole
+//! /24, "no match" when the /24 lies entirely in a gap, or "split" when it is
+//! shared by several ranges (or a range and a gap). Split /24s fall through to
+//! the Memory mode index, every other lookup is a single array read.
+//!
+//! The table takes 64 MiB and a few hundred milliseconds to build, so it can be
+//! saved with [`DirectTable::save`] and handed back to
+//! [`DbSearcher::with_direct_table`] on the next start.
+
+use super::{
+    slice_at, CzdbError, DbSearcher, IndexRecord, IntoCzdbKey, IpType, MemoryIndex, SearchMode, SearchOptions, Section,
+};
+use byteorder::{ByteOrder, LE};
+use std::path::Path;
+
+const MAGIC: &[u8; 4] = b"CZDT";
+const FORMAT_VERSION: u32 = 1;
+/// Magic, format version, fingerprint and record count
+const FILE_HEADER_LENGTH: usize = 20;
+
+/// Entry for a /24 outside every range
+pub(super) const NO_MATCH: u32 = u32::MAX;
+/// Entry for a /24 that has to be resolved through the index
+pub(super) const SPLIT: u32 = u32::MAX - 1;
+
+/// Precomputed /24 lookup table for [`SearchMode::Direct`]
+#[derive(Clone, PartialEq)]
+pub struct DirectTable {
+    pub(super) entries: Vec<u32>,
+    fingerprint: u64,
+    records: u32,
+}
+
+impl DirectTable {
+    /// Number of entries, one per IPv4 /24
+    pub const ENTRIES: usize = 1 << 24;
+
+    /// Number of /24s shared by several ranges, resolved through the index
+    pub fn split_blocks(&self) -> usize {
+        self.entries.iter().filter(|&&entry| entry == SPLIT).count()
+    }
+
+    /// Serialize the table: a 20-byte header followed by the little-endian entries
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(FILE_HEADER_LENGTH + self.entries.len() * 4);
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        out.extend_from_slice(&self.fingerprint.to_le_bytes());
+        out.extend_from_slice(&self.records.to_le_bytes());
+        for entry in &self.entries {
+            out.extend_from_slice(&entry.to_le_bytes());
+        }
+        out
+    }
+
+    /// Parse a table written by [`DirectTable::to_bytes`]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CzdbError> {
+        let header = slice_at(bytes, Section::DirectTable, 0, FILE_HEADER_LENGTH)?;
+        if &header[0..4] != MAGIC || LE::read_u32(&header[4..8]) != FORMAT_VERSION {
+            return Err(CzdbError::InvalidFormat {
+                section: Section::DirectTable,
+                offset: 0,
+                reason: "not a direct table file or unsupported version".to_string(),
+            });
+        }
+        let body = slice_at(bytes, Section::DirectTable, FILE_HEADER_LENGTH, Self::ENTRIES * 4)?;
+        if bytes.len() != FILE_HEADER_LENGTH + body.len() {
+            return Err(CzdbError::InvalidFormat {
+                section: Section::DirectTable,
+                offset: FILE_HEADER_LENGTH + body.len(),
+                reason: format!("{} trailing bytes", bytes.len() - FILE_HEADER_LENGTH - body.len()),
+            });
+        }
+
+        Ok(DirectTable {
+            entries: body.chunks_exact(4).map(LE::read_u32).collect(),
+            fingerprint: LE::read_u64(&header[8..16]),
+            records: LE::read_u32(&header[16..20]),
+        })
+    }
+
+    /// Write the table to a file
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), CzdbError> {
+        Ok(std::fs::write(path, self.to_bytes())?)
+    }
+
+    /// Read a table written by [`DirectTable::save`]
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, CzdbError> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+}
+
+/// Never prints the 16M entries
+impl std::fmt::Debug for DirectTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DirectTable")
+            .field("fingerprint", &format_args!("{:016x}", self.fingerprint))
+            .field("records", &self.records)
+            .finish_non_exhaustive()
+    }
+}
+
+/// FNV-1a over the raw index records, tying a table to its database
+fn fingerprint(index: &[u8]) -> u64 {
+    index
+        .iter()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, &b| (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+impl DbSearcher {
+    /// Create a [`SearchMode::Direct`] searcher from a table saved earlier,
+    /// skipping the table build
+    ///
+    /// Fails with [`CzdbError::InvalidOption`] when the table was built from a
+    /// different database.
+    pub fn with_direct_table(data: Vec<u8>, key: impl IntoCzdbKey, table: DirectTable) -> Result<Self, CzdbError> {
+        let options = SearchOptions { mode: SearchMode::Direct, ..SearchOptions::default() };
+        Self::open(data, key, options, Some(table))
+    }
+
+    /// The /24 table of a [`SearchMode::Direct`] searcher, e.g. to persist it
+    pub fn direct_table(&self) -> Option<&DirectTable> {
+        self.direct_table.as_ref()
+    }
+
+    /// Build the /24 table, or check that a saved one matches this database
+    pub(super) fn build_direct_table(&self, index: &MemoryIndex, saved: Option<DirectTable>) -> Result<DirectTable, CzdbError> {
+        if self.ip_type != IpType::Ipv4 {
+            return Err(CzdbError::InvalidOption("Direct mode requires an IPv4 database".to_string()));
+        }
+        let records = index.data.len() / self.record_len;
+        let fingerprint = fingerprint(&index.data);
+
+        if let Some(table) = saved {
+            if table.fingerprint != fingerprint || table.records as usize != records {
+                return Err(CzdbError::InvalidOption("direct table was built for a different database".to_string()));
+            }
+            return Ok(table);
+        }
+
+        let mut entries = vec![NO_MATCH; DirectTable::ENTRIES];
+        for (idx, bytes) in index.data.chunks_exact(self.record_len).enumerate() {
+            let record = self.parse_record(bytes);
+            if record.start > record.end {
+                continue;
+            }
+            let (first, last) = ((record.start >> 8) as usize, (record.end >> 8) as usize);
+            for (block, entry) in (first..).zip(&mut entries[first..=last]) {
+                let covered = (block << 8) as u128 >= record.start && ((block << 8) | 0xFF) as u128 <= record.end;
+                *entry = if covered && *entry == NO_MATCH { idx as u32 } else { SPLIT };
+            }
+        }
+
+        Ok(DirectTable { entries, fingerprint, records: records as u32 })
+    }
+
+    /// Direct mode: table read, falling through to the index for split /24s
+    pub(super) fn direct_search_ipv4(&self, table: &DirectTable, index: &MemoryIndex, ip: u32) -> Option<IndexRecord> {
+        match *table.entries.get((ip >> 8) as usize)? {
+            NO_MATCH => None,
+            SPLIT => self.memory_search_ipv4(index, ip),
+            idx => self.memory_record(index, idx as usize),
+        }
+    }
+}
@@ -0,0 +1,224 @@
+//! Firewall and routing lists
+//!
+//! [`DbSearcher::region_list`] aggregates the ranges matching a
+//! [`RegionFilter`] into the fewest CIDR blocks and renders them as an
+//! nftables set, an `ipset restore` script, `iptables-restore` rules, a plain
+//! CIDR list or a BIRD static protocol.
+
+use super::reverse::merge_ranges;
+use super::{CzdbError, DbSearcher, IpType, RegionFilter};
+use ipnet::IpNet;
+use std::fmt::{self, Write};
+use std::str::FromStr;
+
+/// Output format of [`DbSearcher::region_list`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ListFormat {
+    /// One CIDR block per line
+    #[default]
+    Cidr,
+    /// A set with interval flags inside a table, for `nft -f`
+    Nftables,
+    /// `hash:net` set commands, for `ipset restore`; a `/0` block is added as
+    /// two `/1` blocks, which `hash:net` accepts
+    Ipset,
+    /// A chain of rules, for `iptables-restore --noflush` or `ip6tables-restore --noflush`
+    Iptables,
+    /// A static protocol for BIRD 1.x, `bird` or `bird6`
+    Bird,
+    /// A static protocol with an `ipv4` or `ipv6` channel for BIRD 2
+    Bird2,
+}
+
+impl ListFormat {
+    /// Lowercase name: `cidr`, `nftables`, `ipset`, `iptables`, `bird`, `bird2`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ListFormat::Cidr => "cidr",
+            ListFormat::Nftables => "nftables",
+            ListFormat::Ipset => "ipset",
+            ListFormat::Iptables => "iptables",
+            ListFormat::Bird => "bird",
+            ListFormat::Bird2 => "bird2",
+        }
+    }
+}
+
+impl FromStr for ListFormat {
+    type Err = CzdbError;
+
+    /// Parse a format name as given by [`ListFormat::as_str`]; `nft` and
+    /// `bird1` are accepted too. Case-insensitive.
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "cidr" => Ok(ListFormat::Cidr),
+            "nftables" | "nft" => Ok(ListFormat::Nftables),
+            "ipset" => Ok(ListFormat::Ipset),
+            "iptables" => Ok(ListFormat::Iptables),
+            "bird" | "bird1" => Ok(ListFormat::Bird),
+            "bird2" => Ok(ListFormat::Bird2),
+            _ => Err(CzdbError::InvalidOption(format!("unknown list format {name:?}"))),
+        }
+    }
+}
+
+impl fmt::Display for ListFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Options of [`DbSearcher::region_list`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListOptions {
+    pub format: ListFormat,
+    /// Name of the nftables set, ipset set, iptables chain or BIRD protocol;
+    /// `None` is `czdb_v4` or `czdb_v6`
+    pub name: Option<String>,
+    /// nftables table holding the set
+    pub table: String,
+    /// Target of the iptables rules
+    pub target: String,
+    /// Whether iptables rules match destination rather than source addresses
+    pub destination: bool,
+    /// What follows the prefix in BIRD routes, e.g. `via 192.0.2.1`,
+    /// `via "eth0"` or `blackhole`
+    pub route: String,
+    /// List every address the filter does not match instead, including
+    /// addresses no range covers; for split routing
+    pub invert: bool,
+}
+
+impl Default for ListOptions {
+    fn default() -> Self {
+        Self {
+            format: ListFormat::Cidr,
+            name: None,
+            table: "inet czdb".to_string(),
+            target: "DROP".to_string(),
+            destination: false,
+            route: "unreachable".to_string(),
+            invert: false,
+        }
+    }
+}
+
+impl ListOptions {
+    /// Render CIDR blocks of one address family in the configured format
+    pub fn render(&self, ip_type: IpType, nets: &[IpNet]) -> String {
+        let mut out = String::with_capacity(nets.len() * 24 + 128);
+        // Writing to a String cannot fail
+        let _ = self.write(ip_type, nets, &mut out);
+        out
+    }
+
+    fn write(&self, ip_type: IpType, nets: &[IpNet], out: &mut String) -> fmt::Result {
+        let name = match (&self.name, ip_type) {
+            (Some(name), _) => name.as_str(),
+            (None, IpType::Ipv4) => "czdb_v4",
+            (None, IpType::Ipv6) => "czdb_v6",
+        };
+        let (nft_type, ipset_family, bird_channel) = match ip_type {
+            IpType::Ipv4 => ("ipv4_addr", "inet", "ipv4"),
+            IpType::Ipv6 => ("ipv6_addr", "inet6", "ipv6"),
+        };
+
+        match self.format {
+            ListFormat::Cidr => {
+                for net in nets {
+                    writeln!(out, "{}", net)?;
+                }
+            }
+            ListFormat::Nftables => {
+                writeln!(out, "table {} {{", self.table)?;
+                writeln!(out, "\tset {} {{", name)?;
+                writeln!(out, "\t\ttype {}", nft_type)?;
+                writeln!(out, "\t\tflags interval")?;
+                // nft rejects an empty element list
+                if !nets.is_empty() {
+                    writeln!(out, "\t\telements = {{")?;
+                    for (i, net) in nets.iter().enumerate() {
+                        writeln!(out, "\t\t\t{}{}", net, if i + 1 < nets.len() { "," } else { "" })?;
+                    }
+                    writeln!(out, "\t\t}}")?;
+                }
+                writeln!(out, "\t}}")?;
+                writeln!(out, "}}")?;
+            }
+            ListFormat::Ipset => {
+                let maxelem = nets.len().max(65536);
+                writeln!(out, "create {} hash:net family {} hashsize 1024 maxelem {} -exist", name, ipset_family, maxelem)?;
+                writeln!(out, "flush {}", name)?;
+                for net in nets {
+                    // hash:net rejects /0, so the whole address space goes in as two halves
+                    match net.subnets(1) {
+                        Ok(halves) if net.prefix_len() == 0 => {
+                            for half in halves {
+                                writeln!(out, "add {} {}", name, half)?;
+                            }
+                        }
+                        _ => writeln!(out, "add {} {}", name, net)?,
+                    }
+                }
+            }
+            ListFormat::Iptables => {
+                let direction = if self.destination { "-d" } else { "-s" };
+                writeln!(out, "*filter")?;
+                writeln!(out, ":{} - [0:0]", name)?;
+                for net in nets {
+                    writeln!(out, "-A {} {} {} -j {}", name, direction, net, self.target)?;
+                }
+                writeln!(out, "COMMIT")?;
+            }
+            ListFormat::Bird | ListFormat::Bird2 => {
+                writeln!(out, "protocol static {} {{", name)?;
+                if self.format == ListFormat::Bird2 {
+                    writeln!(out, "\t{};", bird_channel)?;
+                }
+                for net in nets {
+                    writeln!(out, "\troute {} {};", net, self.route)?;
+                }
+                writeln!(out, "}}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl DbSearcher {
+    /// Render the ranges whose region matches `filter` as a firewall or routing list
+    ///
+    /// The ranges are merged and split into the fewest CIDR blocks, as
+    /// [`DbSearcher::region_cidrs`] does; with [`ListOptions::invert`] the
+    /// blocks cover the rest of the address space instead. The list has the
+    /// address family of the database.
+    pub fn region_list(&self, filter: &RegionFilter, options: &ListOptions) -> Result<String, CzdbError> {
+        let mut ranges = merge_ranges(self.matching_records(filter)?.into_iter().map(|(record, _)| (record.start, record.end)));
+        if options.invert {
+            ranges = self.complement(&ranges);
+        }
+        Ok(options.render(self.ip_type, &self.aggregate(ranges)))
+    }
+
+    /// Gaps between sorted, merged ranges across the whole address space
+    fn complement(&self, ranges: &[(u128, u128)]) -> Vec<(u128, u128)> {
+        let max = match self.ip_type {
+            IpType::Ipv4 => u32::MAX as u128,
+            IpType::Ipv6 => u128::MAX,
+        };
+        let mut gaps = Vec::new();
+        let mut next = Some(0u128);
+        for &(start, end) in ranges {
+            if let Some(from) = next {
+                if start > from {
+                    gaps.push((from, start - 1));
+                }
+            }
+            next = end.checked_add(1).filter(|&n| n <= max);
+        }
+        if let Some(from) = next {
+            gaps.push((from, max));
+        }
+        gaps
+    }
+}
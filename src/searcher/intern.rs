@@ -0,0 +1,102 @@
+//! Interned region strings
+//!
+//! A database has far fewer distinct regions than ranges. With
+//! [`SearchOptions::intern_regions`](super::SearchOptions::intern_regions) every
+//! region is decoded once when the searcher is built, and lookups copy or share
+//! the decoded string instead of decoding msgpack again.
+
+use super::{CzdbError, DbSearcher, IndexRecord};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Decoded regions keyed by the (data pointer, length) of their region record
+#[derive(Debug, Clone, Default)]
+pub(super) struct RegionTable {
+    ids: HashMap<(usize, usize), u32>,
+    regions: Vec<Arc<str>>,
+}
+
+impl RegionTable {
+    pub(super) fn get(&self, ptr: usize, len: usize) -> Option<&Arc<str>> {
+        self.ids.get(&(ptr, len)).map(|&id| &self.regions[id as usize])
+    }
+}
+
+impl DbSearcher {
+    /// Search into a caller-owned buffer, without allocating once `out` has
+    /// grown to fit the longest region
+    ///
+    /// `out` is cleared first. Returns `false` and leaves `out` empty when no
+    /// range covers the address.
+    pub fn search_into(&self, ip: &str, out: &mut String) -> Result<bool, CzdbError> {
+        out.clear();
+        let ip = IpAddr::from_str(ip)?;
+        if let Some(cache) = &self.cache {
+            let range = self.cached_locate(cache, ip)?;
+            out.push_str(range.as_ref().map_or("", |r| &r.region));
+            return Ok(range.is_some());
+        }
+        match self.locate(ip)? {
+            Some(record) => {
+                self.write_region(record.data_ptr, record.data_len, out)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Search and return a shared region string, or `None` when no range
+    /// covers the address
+    ///
+    /// With interned regions or a result cache hit this is a reference count
+    /// increment; otherwise the region is decoded into a new allocation.
+    pub fn search_shared(&self, ip: &str) -> Result<Option<Arc<str>>, CzdbError> {
+        let ip = IpAddr::from_str(ip)?;
+        if let Some(cache) = &self.cache {
+            return Ok(self.cached_locate(cache, ip)?.map(|range| range.region));
+        }
+        match self.locate(ip)? {
+            Some(record) => match self.region_table.as_ref().and_then(|t| t.get(record.data_ptr, record.data_len)) {
+                Some(region) => Ok(Some(Arc::clone(region))),
+                None => Ok(Some(self.get_region(record.data_ptr, record.data_len)?.into())),
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Decode every distinct region referenced by the index
+    ///
+    /// Records whose region fails to decode are left out, so lookups hitting
+    /// them decode again and report the error as they would without interning.
+    pub(super) fn build_region_table(&self, records: impl Iterator<Item = IndexRecord>) -> RegionTable {
+        let mut table = RegionTable::default();
+        let mut by_value: HashMap<Arc<str>, u32> = HashMap::new();
+        let mut region = String::new();
+
+        for record in records {
+            let key = (record.data_ptr, record.data_len);
+            if table.ids.contains_key(&key) {
+                continue;
+            }
+            region.clear();
+            if self.decode_region(record.data_ptr, record.data_len, &mut region).is_err() {
+                continue;
+            }
+            let id = match by_value.get(region.as_str()) {
+                Some(&id) => id,
+                None => {
+                    let id = table.regions.len() as u32;
+                    let shared: Arc<str> = region.as_str().into();
+                    by_value.insert(Arc::clone(&shared), id);
+                    table.regions.push(shared);
+                    id
+                }
+            };
+            table.ids.insert(key, id);
+        }
+
+        table
+    }
+}
@@ -0,0 +1,129 @@
+//! ISO 3166 codes
+//!
+//! Maps the Chinese country names CZ88 uses to ISO 3166-1 alpha-2/alpha-3
+//! codes, and Chinese province-level divisions to ISO 3166-2:CN codes.
+
+/// Countries and territories as (alpha-2, alpha-3, Chinese name)
+const COUNTRIES: &[(&str, &str, &str)] = &[
+    ("AD", "AND", "安道尔"), ("AE", "ARE", "阿联酋"), ("AF", "AFG", "阿富汗"), ("AG", "ATG", "安提瓜和巴布达"),
+    ("AI", "AIA", "安圭拉"), ("AL", "ALB", "阿尔巴尼亚"), ("AM", "ARM", "亚美尼亚"), ("AO", "AGO", "安哥拉"),
+    ("AQ", "ATA", "南极洲"), ("AR", "ARG", "阿根廷"), ("AS", "ASM", "美属萨摩亚"), ("AT", "AUT", "奥地利"),
+    ("AU", "AUS", "澳大利亚"), ("AW", "ABW", "阿鲁巴"), ("AX", "ALA", "奥兰群岛"), ("AZ", "AZE", "阿塞拜疆"),
+    ("BA", "BIH", "波黑"), ("BB", "BRB", "巴巴多斯"), ("BD", "BGD", "孟加拉"), ("BE", "BEL", "比利时"),
+    ("BF", "BFA", "布基纳法索"), ("BG", "BGR", "保加利亚"), ("BH", "BHR", "巴林"), ("BI", "BDI", "布隆迪"),
+    ("BJ", "BEN", "贝宁"), ("BL", "BLM", "圣巴泰勒米"), ("BM", "BMU", "百慕大"), ("BN", "BRN", "文莱"),
+    ("BO", "BOL", "玻利维亚"), ("BQ", "BES", "荷兰加勒比区"), ("BR", "BRA", "巴西"), ("BS", "BHS", "巴哈马"),
+    ("BT", "BTN", "不丹"), ("BV", "BVT", "布韦岛"), ("BW", "BWA", "博茨瓦纳"), ("BY", "BLR", "白俄罗斯"),
+    ("BZ", "BLZ", "伯利兹"), ("CA", "CAN", "加拿大"), ("CC", "CCK", "科科斯群岛"), ("CD", "COD", "刚果(金)"),
+    ("CF", "CAF", "中非"), ("CG", "COG", "刚果(布)"), ("CH", "CHE", "瑞士"), ("CI", "CIV", "科特迪瓦"),
+    ("CK", "COK", "库克群岛"), ("CL", "CHL", "智利"), ("CM", "CMR", "喀麦隆"), ("CN", "CHN", "中国"),
+    ("CO", "COL", "哥伦比亚"), ("CR", "CRI", "哥斯达黎加"), ("CU", "CUB", "古巴"), ("CV", "CPV", "佛得角"),
+    ("CW", "CUW", "库拉索"), ("CX", "CXR", "圣诞岛"), ("CY", "CYP", "塞浦路斯"), ("CZ", "CZE", "捷克"),
+    ("DE", "DEU", "德国"), ("DJ", "DJI", "吉布提"), ("DK", "DNK", "丹麦"), ("DM", "DMA", "多米尼克"),
+    ("DO", "DOM", "多米尼加"), ("DZ", "DZA", "阿尔及利亚"), ("EC", "ECU", "厄瓜多尔"), ("EE", "EST", "爱沙尼亚"),
+    ("EG", "EGY", "埃及"), ("EH", "ESH", "西撒哈拉"), ("ER", "ERI", "厄立特里亚"), ("ES", "ESP", "西班牙"),
+    ("ET", "ETH", "埃塞俄比亚"), ("FI", "FIN", "芬兰"), ("FJ", "FJI", "斐济"), ("FK", "FLK", "福克兰群岛"),
+    ("FM", "FSM", "密克罗尼西亚"), ("FO", "FRO", "法罗群岛"), ("FR", "FRA", "法国"), ("GA", "GAB", "加蓬"),
+    ("GB", "GBR", "英国"), ("GD", "GRD", "格林纳达"), ("GE", "GEO", "格鲁吉亚"), ("GF", "GUF", "法属圭亚那"),
+    ("GG", "GGY", "根西岛"), ("GH", "GHA", "加纳"), ("GI", "GIB", "直布罗陀"), ("GL", "GRL", "格陵兰"),
+    ("GM", "GMB", "冈比亚"), ("GN", "GIN", "几内亚"), ("GP", "GLP", "瓜德罗普"), ("GQ", "GNQ", "赤道几内亚"),
+    ("GR", "GRC", "希腊"), ("GS", "SGS", "南乔治亚和南桑威奇群岛"), ("GT", "GTM", "危地马拉"), ("GU", "GUM", "关岛"),
+    ("GW", "GNB", "几内亚比绍"), ("GY", "GUY", "圭亚那"), ("HK", "HKG", "香港"), ("HM", "HMD", "赫德岛和麦克唐纳群岛"),
+    ("HN", "HND", "洪都拉斯"), ("HR", "HRV", "克罗地亚"), ("HT", "HTI", "海地"), ("HU", "HUN", "匈牙利"),
+    ("ID", "IDN", "印度尼西亚"), ("IE", "IRL", "爱尔兰"), ("IL", "ISR", "以色列"), ("IM", "IMN", "马恩岛"),
+    ("IN", "IND", "印度"), ("IO", "IOT", "英属印度洋领地"), ("IQ", "IRQ", "伊拉克"), ("IR", "IRN", "伊朗"),
+    ("IS", "ISL", "冰岛"), ("IT", "ITA", "意大利"), ("JE", "JEY", "泽西岛"), ("JM", "JAM", "牙买加"),
+    ("JO", "JOR", "约旦"), ("JP", "JPN", "日本"), ("KE", "KEN", "肯尼亚"), ("KG", "KGZ", "吉尔吉斯斯坦"),
+    ("KH", "KHM", "柬埔寨"), ("KI", "KIR", "基里巴斯"), ("KM", "COM", "科摩罗"), ("KN", "KNA", "圣基茨和尼维斯"),
+    ("KP", "PRK", "朝鲜"), ("KR", "KOR", "韩国"), ("KW", "KWT", "科威特"), ("KY", "CYM", "开曼群岛"),
+    ("KZ", "KAZ", "哈萨克斯坦"), ("LA", "LAO", "老挝"), ("LB", "LBN", "黎巴嫩"), ("LC", "LCA", "圣卢西亚"),
+    ("LI", "LIE", "列支敦士登"), ("LK", "LKA", "斯里兰卡"), ("LR", "LBR", "利比里亚"), ("LS", "LSO", "莱索托"),
+    ("LT", "LTU", "立陶宛"), ("LU", "LUX", "卢森堡"), ("LV", "LVA", "拉脱维亚"), ("LY", "LBY", "利比亚"),
+    ("MA", "MAR", "摩洛哥"), ("MC", "MCO", "摩纳哥"), ("MD", "MDA", "摩尔多瓦"), ("ME", "MNE", "黑山"),
+    ("MF", "MAF", "法属圣马丁"), ("MG", "MDG", "马达加斯加"), ("MH", "MHL", "马绍尔群岛"), ("MK", "MKD", "北马其顿"),
+    ("ML", "MLI", "马里"), ("MM", "MMR", "缅甸"), ("MN", "MNG", "蒙古"), ("MO", "MAC", "澳门"),
+    ("MP", "MNP", "北马里亚纳群岛"), ("MQ", "MTQ", "马提尼克"), ("MR", "MRT", "毛里塔尼亚"), ("MS", "MSR", "蒙特塞拉特"),
+    ("MT", "MLT", "马耳他"), ("MU", "MUS", "毛里求斯"), ("MV", "MDV", "马尔代夫"), ("MW", "MWI", "马拉维"),
+    ("MX", "MEX", "墨西哥"), ("MY", "MYS", "马来西亚"), ("MZ", "MOZ", "莫桑比克"), ("NA", "NAM", "纳米比亚"),
+    ("NC", "NCL", "新喀里多尼亚"), ("NE", "NER", "尼日尔"), ("NF", "NFK", "诺福克岛"), ("NG", "NGA", "尼日利亚"),
+    ("NI", "NIC", "尼加拉瓜"), ("NL", "NLD", "荷兰"), ("NO", "NOR", "挪威"), ("NP", "NPL", "尼泊尔"),
+    ("NR", "NRU", "瑙鲁"), ("NU", "NIU", "纽埃"), ("NZ", "NZL", "新西兰"), ("OM", "OMN", "阿曼"),
+    ("PA", "PAN", "巴拿马"), ("PE", "PER", "秘鲁"), ("PF", "PYF", "法属波利尼西亚"), ("PG", "PNG", "巴布亚新几内亚"),
+    ("PH", "PHL", "菲律宾"), ("PK", "PAK", "巴基斯坦"), ("PL", "POL", "波兰"), ("PM", "SPM", "圣皮埃尔和密克隆"),
+    ("PN", "PCN", "皮特凯恩群岛"), ("PR", "PRI", "波多黎各"), ("PS", "PSE", "巴勒斯坦"), ("PT", "PRT", "葡萄牙"),
+    ("PW", "PLW", "帕劳"), ("PY", "PRY", "巴拉圭"), ("QA", "QAT", "卡塔尔"), ("RE", "REU", "留尼汪"),
+    ("RO", "ROU", "罗马尼亚"), ("RS", "SRB", "塞尔维亚"), ("RU", "RUS", "俄罗斯"), ("RW", "RWA", "卢旺达"),
+    ("SA", "SAU", "沙特阿拉伯"), ("SB", "SLB", "所罗门群岛"), ("SC", "SYC", "塞舌尔"), ("SD", "SDN", "苏丹"),
+    ("SE", "SWE", "瑞典"), ("SG", "SGP", "新加坡"), ("SH", "SHN", "圣赫勒拿"), ("SI", "SVN", "斯洛文尼亚"),
+    ("SJ", "SJM", "斯瓦尔巴和扬马延"), ("SK", "SVK", "斯洛伐克"), ("SL", "SLE", "塞拉利昂"), ("SM", "SMR", "圣马力诺"),
+    ("SN", "SEN", "塞内加尔"), ("SO", "SOM", "索马里"), ("SR", "SUR", "苏里南"), ("SS", "SSD", "南苏丹"),
+    ("ST", "STP", "圣多美和普林西比"), ("SV", "SLV", "萨尔瓦多"), ("SX", "SXM", "荷属圣马丁"), ("SY", "SYR", "叙利亚"),
+    ("SZ", "SWZ", "斯威士兰"), ("TC", "TCA", "特克斯和凯科斯群岛"), ("TD", "TCD", "乍得"), ("TF", "ATF", "法属南部领地"),
+    ("TG", "TGO", "多哥"), ("TH", "THA", "泰国"), ("TJ", "TJK", "塔吉克斯坦"), ("TK", "TKL", "托克劳"),
+    ("TL", "TLS", "东帝汶"), ("TM", "TKM", "土库曼斯坦"), ("TN", "TUN", "突尼斯"), ("TO", "TON", "汤加"),
+    ("TR", "TUR", "土耳其"), ("TT", "TTO", "特立尼达和多巴哥"), ("TV", "TUV", "图瓦卢"), ("TW", "TWN", "台湾"),
+    ("TZ", "TZA", "坦桑尼亚"), ("UA", "UKR", "乌克兰"), ("UG", "UGA", "乌干达"), ("UM", "UMI", "美国本土外小岛屿"),
+    ("US", "USA", "美国"), ("UY", "URY", "乌拉圭"), ("UZ", "UZB", "乌兹别克斯坦"), ("VA", "VAT", "梵蒂冈"),
+    ("VC", "VCT", "圣文森特和格林纳丁斯"), ("VE", "VEN", "委内瑞拉"), ("VG", "VGB", "英属维尔京群岛"),
+    ("VI", "VIR", "美属维尔京群岛"), ("VN", "VNM", "越南"), ("VU", "VUT", "瓦努阿图"), ("WF", "WLF", "瓦利斯和富图纳"),
+    ("WS", "WSM", "萨摩亚"), ("YE", "YEM", "也门"), ("YT", "MYT", "马约特"), ("ZA", "ZAF", "南非"),
+    ("ZM", "ZMB", "赞比亚"), ("ZW", "ZWE", "津巴布韦"),
+];
+
+/// Other names for the same country, as (alias, alpha-2)
+const ALIASES: &[(&str, &str)] = &[
+    ("中华人民共和国", "CN"), ("阿拉伯联合酋长国", "AE"), ("波斯尼亚和黑塞哥维那", "BA"), ("孟加拉国", "BD"),
+    ("刚果民主共和国", "CD"), ("刚果共和国", "CG"), ("中非共和国", "CF"), ("捷克共和国", "CZ"),
+    ("多米尼加共和国", "DO"), ("联合王国", "GB"), ("大不列颠及北爱尔兰联合王国", "GB"), ("印尼", "ID"),
+    ("北朝鲜", "KP"), ("南韩", "KR"), ("大韩民国", "KR"), ("老挝人民民主共和国", "LA"), ("马其顿", "MK"),
+    ("蒙古国", "MN"), ("俄罗斯联邦", "RU"), ("沙特", "SA"), ("埃斯瓦蒂尼", "SZ"), ("美利坚合众国", "US"),
+    ("梵蒂冈城国", "VA"), ("象牙海岸", "CI"), ("澳洲", "AU"), ("纽西兰", "NZ"),
+];
+
+/// ISO 3166-2:CN codes by GB/T 2260 province code
+const CN_SUBDIVISIONS: &[(u32, &str)] = &[
+    (110000, "CN-BJ"), (120000, "CN-TJ"), (130000, "CN-HE"), (140000, "CN-SX"), (150000, "CN-NM"),
+    (210000, "CN-LN"), (220000, "CN-JL"), (230000, "CN-HL"), (310000, "CN-SH"), (320000, "CN-JS"),
+    (330000, "CN-ZJ"), (340000, "CN-AH"), (350000, "CN-FJ"), (360000, "CN-JX"), (370000, "CN-SD"),
+    (410000, "CN-HA"), (420000, "CN-HB"), (430000, "CN-HN"), (440000, "CN-GD"), (450000, "CN-GX"),
+    (460000, "CN-HI"), (500000, "CN-CQ"), (510000, "CN-SC"), (520000, "CN-GZ"), (530000, "CN-YN"),
+    (540000, "CN-XZ"), (610000, "CN-SN"), (620000, "CN-GS"), (630000, "CN-QH"), (640000, "CN-NX"),
+    (650000, "CN-XJ"), (710000, "CN-TW"), (810000, "CN-HK"), (820000, "CN-MO"),
+];
+
+/// A country or territory with its ISO 3166-1 codes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Country {
+    /// Alpha-2 code, e.g. `US`
+    pub alpha2: &'static str,
+    /// Alpha-3 code, e.g. `USA`
+    pub alpha3: &'static str,
+    /// Chinese name as CZ88 writes it, e.g. `美国`
+    pub name: &'static str,
+}
+
+impl Country {
+    /// Country by its Chinese name, as in region text, or a common alias
+    pub fn from_name(name: &str) -> Option<Self> {
+        if let Some(&(_, alpha2)) = ALIASES.iter().find(|&&(alias, _)| alias == name) {
+            return Self::from_code(alpha2);
+        }
+        COUNTRIES
+            .iter()
+            .find(|&&(.., n)| n == name)
+            .map(|&(alpha2, alpha3, name)| Country { alpha2, alpha3, name })
+    }
+
+    /// Country by its alpha-2 or alpha-3 code, case-insensitive
+    pub fn from_code(code: &str) -> Option<Self> {
+        COUNTRIES
+            .iter()
+            .find(|&&(a2, a3, _)| a2.eq_ignore_ascii_case(code) || a3.eq_ignore_ascii_case(code))
+            .map(|&(alpha2, alpha3, name)| Country { alpha2, alpha3, name })
+    }
+}
+
+/// ISO 3166-2:CN code of the province-level division with GB/T 2260 code `province`
+pub(super) fn cn_subdivision(province: u32) -> Option<&'static str> {
+    CN_SUBDIVISIONS.iter().find(|&&(code, _)| code == province).map(|&(_, iso)| iso)
+}
@@ -0,0 +1,172 @@
+//! ISP normalization
+//!
+//! The ISP part of CZ88 region text is free-form: `电信`, `联通/IDC机房`,
+//! `阿里云BGP数据中心`, `鹏博士宽带`. [`Carrier::from_isp`] maps it to a
+//! canonical carrier and [`IspCategory::classify`] to the kind of network,
+//! both by keyword. ASCII keywords only match whole words.
+
+use std::fmt;
+
+/// Kind of network an ISP string describes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum IspCategory {
+    /// Fixed-line and cable broadband
+    Residential,
+    /// Cellular data
+    Mobile,
+    /// Education and research networks, universities
+    Education,
+    /// Data centers, cloud providers, CDNs and IDC rooms
+    Datacenter,
+    #[default]
+    Unknown,
+}
+
+impl IspCategory {
+    /// Lowercase name: `residential`, `mobile`, `education`, `datacenter`, `unknown`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IspCategory::Residential => "residential",
+            IspCategory::Mobile => "mobile",
+            IspCategory::Education => "education",
+            IspCategory::Datacenter => "datacenter",
+            IspCategory::Unknown => "unknown",
+        }
+    }
+
+    /// Classify an ISP string
+    ///
+    /// Keywords decide first, in order data center, education, mobile and
+    /// broadband, so `联通/IDC机房` is a data center and `移动/数据上网公共出口`
+    /// mobile data; otherwise the category of the [`Carrier`] applies.
+    pub fn classify(isp: &str) -> Self {
+        let isp = isp.to_lowercase();
+        let keyword = [
+            (IspCategory::Datacenter, DATACENTER_KEYWORDS),
+            (IspCategory::Education, EDUCATION_KEYWORDS),
+            (IspCategory::Mobile, MOBILE_KEYWORDS),
+            (IspCategory::Residential, RESIDENTIAL_KEYWORDS),
+        ]
+        .into_iter()
+        .find(|(_, keywords)| keywords.iter().any(|keyword| mentions(&isp, keyword)));
+        match keyword {
+            Some((category, _)) => category,
+            None => carrier(&isp).map_or(IspCategory::Unknown, |carrier| carrier.category),
+        }
+    }
+}
+
+impl fmt::Display for IspCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Generic words a keyword must not run into: `科技网` is CSTNET but
+/// `科技网络有限公司` any company
+const GENERIC_WORDS: &[&str] = &["网络", "通信"];
+
+/// Keywords of data center and cloud ISP strings, lowercase
+const DATACENTER_KEYWORDS: &[&str] = &[
+    "数据中心", "idc", "机房", "bgp", "cdn", "云计算", "节点", "anycast", "服务器", "托管", "云数据",
+    "cloud", "hosting", "data center", "datacenter", "vps",
+];
+
+/// Keywords of education and research networks, lowercase
+const EDUCATION_KEYWORDS: &[&str] = &["教育", "大学", "学院", "学校", "中学", "科技网", "university", "college", "school"];
+
+/// Keywords of cellular data, lowercase
+const MOBILE_KEYWORDS: &[&str] = &["数据上网公共出口", "gsm", "cdma", "移动网", "无线"];
+
+/// Keywords of fixed-line broadband, lowercase
+const RESIDENTIAL_KEYWORDS: &[&str] = &["宽带", "宽频", "铁通", "有线", "光纤", "ftth", "adsl", "broadband"];
+
+/// Carriers as (id, name, category, lowercase keywords), cloud providers and
+/// broadband resellers before the backbone carriers they buy from
+const CARRIERS: &[(&str, &str, IspCategory, &[&str])] = &[
+    ("aliyun", "阿里云", IspCategory::Datacenter, &["阿里云", "阿里巴巴", "淘宝", "alibaba", "aliyun"]),
+    ("tencentcloud", "腾讯云", IspCategory::Datacenter, &["腾讯", "tencent"]),
+    ("huaweicloud", "华为云", IspCategory::Datacenter, &["华为云", "huaweicloud"]),
+    ("baiducloud", "百度智能云", IspCategory::Datacenter, &["百度", "baidu"]),
+    ("jdcloud", "京东云", IspCategory::Datacenter, &["京东", "jdcloud"]),
+    ("kingsoftcloud", "金山云", IspCategory::Datacenter, &["金山云", "ksyun"]),
+    ("ucloud", "优刻得", IspCategory::Datacenter, &["优刻得", "ucloud"]),
+    ("ctyun", "天翼云", IspCategory::Datacenter, &["天翼云", "电信云"]),
+    ("meituancloud", "美团云", IspCategory::Datacenter, &["美团", "三快"]),
+    ("netease", "网易", IspCategory::Datacenter, &["网易", "netease"]),
+    ("wangsu", "网宿科技", IspCategory::Datacenter, &["网宿", "chinanetcenter"]),
+    ("chinacache", "蓝汛", IspCategory::Datacenter, &["蓝汛", "chinacache"]),
+    ("qiniu", "七牛云", IspCategory::Datacenter, &["七牛", "qiniu"]),
+    ("21vianet", "世纪互联", IspCategory::Datacenter, &["世纪互联", "21vianet"]),
+    ("gds", "万国数据", IspCategory::Datacenter, &["万国数据"]),
+    ("sinnet", "光环新网", IspCategory::Datacenter, &["光环新网", "sinnet"]),
+    ("aws", "Amazon Web Services", IspCategory::Datacenter, &["亚马逊", "amazon", "aws"]),
+    ("microsoft", "Microsoft", IspCategory::Datacenter, &["微软", "microsoft", "azure"]),
+    ("google", "Google", IspCategory::Datacenter, &["谷歌", "google"]),
+    ("cloudflare", "Cloudflare", IspCategory::Datacenter, &["cloudflare"]),
+    ("akamai", "Akamai", IspCategory::Datacenter, &["akamai"]),
+    ("oracle", "Oracle Cloud", IspCategory::Datacenter, &["甲骨文", "oracle"]),
+    ("digitalocean", "DigitalOcean", IspCategory::Datacenter, &["digitalocean"]),
+    ("linode", "Linode", IspCategory::Datacenter, &["linode"]),
+    ("vultr", "Vultr", IspCategory::Datacenter, &["vultr", "choopa"]),
+    ("ovh", "OVH", IspCategory::Datacenter, &["ovh"]),
+    ("hetzner", "Hetzner", IspCategory::Datacenter, &["hetzner"]),
+    ("zenlayer", "Zenlayer", IspCategory::Datacenter, &["zenlayer"]),
+    ("drpeng", "鹏博士", IspCategory::Residential, &["鹏博士", "长城宽带", "宽带通"]),
+    ("chinabroadnet", "中国广电", IspCategory::Residential, &["广电", "有线", "歌华", "华数", "天威", "珠江宽频"]),
+    ("cernet", "中国教育和科研计算机网", IspCategory::Education, &["教育网", "教育信息网", "cernet"]),
+    ("cstnet", "中国科技网", IspCategory::Education, &["科技网", "cstnet"]),
+    ("chinatelecom", "中国电信", IspCategory::Residential, &["电信", "chinanet", "china telecom", "chinatelecom"]),
+    ("chinaunicom", "中国联通", IspCategory::Residential, &["联通", "网通", "unicom"]),
+    ("chinamobile", "中国移动", IspCategory::Mobile, &["移动", "中移", "铁通", "china mobile", "chinamobile", "cmnet"]),
+];
+
+/// A carrier with its canonical id
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Carrier {
+    /// Stable lowercase id, e.g. `chinatelecom`, `aliyun`
+    pub id: &'static str,
+    /// Canonical name, e.g. `中国电信`
+    pub name: &'static str,
+    /// Main business of the carrier; a given ISP string may still classify
+    /// differently, see [`IspCategory::classify`]
+    pub category: IspCategory,
+}
+
+impl Carrier {
+    /// Carrier an ISP string names, e.g. `阿里云BGP数据中心` → `aliyun`
+    pub fn from_isp(isp: &str) -> Option<Self> {
+        carrier(&isp.to_lowercase())
+    }
+
+    /// Carrier by its id
+    pub fn from_id(id: &str) -> Option<Self> {
+        CARRIERS.iter().find(|&&(i, ..)| i == id).map(|&(id, name, category, _)| Carrier { id, name, category })
+    }
+}
+
+/// First carrier whose keywords occur in a lowercase ISP string
+fn carrier(isp: &str) -> Option<Carrier> {
+    CARRIERS
+        .iter()
+        .find(|(.., keywords)| keywords.iter().any(|keyword| mentions(isp, keyword)))
+        .map(|&(id, name, category, _)| Carrier { id, name, category })
+}
+
+/// Whether `keyword` occurs in `isp` other than as the start of a generic word,
+/// and, for an ASCII keyword, as a whole word: `ovh` is not in `covhost`
+fn mentions(isp: &str, keyword: &str) -> bool {
+    isp.match_indices(keyword).any(|(pos, _)| {
+        let Some(last) = keyword.chars().last() else {
+            return false;
+        };
+        let rest = &isp[pos + keyword.len()..];
+        if keyword.is_ascii() {
+            let alphanumeric = |c: Option<char>| c.is_some_and(|c| c.is_ascii_alphanumeric());
+            if alphanumeric(isp[..pos].chars().next_back()) || alphanumeric(rest.chars().next()) {
+                return false;
+            }
+        }
+        !GENERIC_WORDS.iter().any(|word| word.strip_prefix(last).is_some_and(|tail| rest.starts_with(tail)))
+    })
+}
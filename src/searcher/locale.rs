@@ -0,0 +1,279 @@
+//! Localized place names
+//!
+//! [`Location::localized`] renders the area, country, province and city of a
+//! parsed region in another [`Language`]. English and Hanyu Pinyin come from
+//! tables covering countries, areas and Chinese province- and prefecture-level
+//! divisions; names outside them are kept as stored. Traditional Chinese is a
+//! character conversion applied to every level down to the district. The ISP
+//! and the codes are never changed.
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::{AdminDivision, CzdbError, Location};
+
+/// Language of the place names in a [`Location`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Language {
+    /// Simplified Chinese, as stored in the database
+    #[default]
+    Chinese,
+    /// Traditional Chinese characters
+    TraditionalChinese,
+    /// English exonyms where one exists (`Inner Mongolia`, `Urumqi`),
+    /// otherwise Pinyin
+    English,
+    /// Hanyu Pinyin of Chinese place names (`Neimenggu`, `Wulumuqi`); foreign
+    /// countries and areas use their English name
+    Pinyin,
+}
+
+impl FromStr for Language {
+    type Err = CzdbError;
+
+    /// Parse a language tag: `zh`, `zh-CN` or `zh-Hans`; `zh-Hant`, `zh-TW`
+    /// or `zh-HK`; `en`; `pinyin` or `zh-Latn`. Case-insensitive.
+    fn from_str(tag: &str) -> Result<Self, Self::Err> {
+        let tag = tag.trim().replace('_', "-").to_ascii_lowercase();
+        match tag.as_str() {
+            "zh" | "zh-cn" | "zh-sg" | "zh-hans" | "zh-hans-cn" => Ok(Language::Chinese),
+            "zh-hant" | "zh-tw" | "zh-hk" | "zh-mo" | "zh-hant-tw" | "zh-hant-hk" => Ok(Language::TraditionalChinese),
+            "en" => Ok(Language::English),
+            "pinyin" | "zh-latn" | "zh-latn-pinyin" => Ok(Language::Pinyin),
+            _ if tag.starts_with("en-") => Ok(Language::English),
+            _ => Err(CzdbError::InvalidOption(format!("unknown language {tag:?}"))),
+        }
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Language::Chinese => "zh-Hans",
+            Language::TraditionalChinese => "zh-Hant",
+            Language::English => "en",
+            Language::Pinyin => "zh-Latn",
+        })
+    }
+}
+
+/// English names of the areas CZ88 gives in place of a country
+const AREAS: &[(&str, &str)] = &[
+    ("亚洲", "Asia"), ("欧洲", "Europe"), ("非洲", "Africa"), ("北美洲", "North America"),
+    ("南美洲", "South America"), ("大洋洲", "Oceania"), ("南极洲", "Antarctica"),
+    ("亚太地区", "Asia Pacific"), ("北美地区", "North America"), ("欧洲地区", "Europe"),
+    ("非洲地区", "Africa"), ("拉美地区", "Latin America"), ("中东地区", "Middle East"),
+    ("IANA", "IANA"), ("未分配地址", "Unallocated"), ("保留地址", "Reserved"),
+    ("共享地址", "Shared Address Space"), ("组播地址", "Multicast"), ("局域网", "LAN"),
+    ("本机地址", "Localhost"), ("本地回环", "Loopback"),
+];
+
+/// English country names by ISO 3166-1 alpha-2 code
+const COUNTRIES: &[(&str, &str)] = &[
+    ("AD", "Andorra"), ("AE", "United Arab Emirates"), ("AF", "Afghanistan"), ("AG", "Antigua and Barbuda"),
+    ("AI", "Anguilla"), ("AL", "Albania"), ("AM", "Armenia"), ("AO", "Angola"), ("AQ", "Antarctica"),
+    ("AR", "Argentina"), ("AS", "American Samoa"), ("AT", "Austria"), ("AU", "Australia"), ("AW", "Aruba"),
+    ("AX", "Åland Islands"), ("AZ", "Azerbaijan"), ("BA", "Bosnia and Herzegovina"), ("BB", "Barbados"),
+    ("BD", "Bangladesh"), ("BE", "Belgium"), ("BF", "Burkina Faso"), ("BG", "Bulgaria"), ("BH", "Bahrain"),
+    ("BI", "Burundi"), ("BJ", "Benin"), ("BL", "Saint Barthélemy"), ("BM", "Bermuda"), ("BN", "Brunei"),
+    ("BO", "Bolivia"), ("BQ", "Caribbean Netherlands"), ("BR", "Brazil"), ("BS", "Bahamas"), ("BT", "Bhutan"),
+    ("BV", "Bouvet Island"), ("BW", "Botswana"), ("BY", "Belarus"), ("BZ", "Belize"), ("CA", "Canada"),
+    ("CC", "Cocos (Keeling) Islands"), ("CD", "DR Congo"), ("CF", "Central African Republic"),
+    ("CG", "Republic of the Congo"), ("CH", "Switzerland"), ("CI", "Côte d'Ivoire"), ("CK", "Cook Islands"),
+    ("CL", "Chile"), ("CM", "Cameroon"), ("CN", "China"), ("CO", "Colombia"), ("CR", "Costa Rica"),
+    ("CU", "Cuba"), ("CV", "Cape Verde"), ("CW", "Curaçao"), ("CX", "Christmas Island"), ("CY", "Cyprus"),
+    ("CZ", "Czechia"), ("DE", "Germany"), ("DJ", "Djibouti"), ("DK", "Denmark"), ("DM", "Dominica"),
+    ("DO", "Dominican Republic"), ("DZ", "Algeria"), ("EC", "Ecuador"), ("EE", "Estonia"), ("EG", "Egypt"),
+    ("EH", "Western Sahara"), ("ER", "Eritrea"), ("ES", "Spain"), ("ET", "Ethiopia"), ("FI", "Finland"),
+    ("FJ", "Fiji"), ("FK", "Falkland Islands"), ("FM", "Micronesia"), ("FO", "Faroe Islands"),
+    ("FR", "France"), ("GA", "Gabon"), ("GB", "United Kingdom"), ("GD", "Grenada"), ("GE", "Georgia"),
+    ("GF", "French Guiana"), ("GG", "Guernsey"), ("GH", "Ghana"), ("GI", "Gibraltar"), ("GL", "Greenland"),
+    ("GM", "Gambia"), ("GN", "Guinea"), ("GP", "Guadeloupe"), ("GQ", "Equatorial Guinea"), ("GR", "Greece"),
+    ("GS", "South Georgia and the South Sandwich Islands"), ("GT", "Guatemala"), ("GU", "Guam"),
+    ("GW", "Guinea-Bissau"), ("GY", "Guyana"), ("HK", "Hong Kong"),
+    ("HM", "Heard Island and McDonald Islands"), ("HN", "Honduras"), ("HR", "Croatia"), ("HT", "Haiti"),
+    ("HU", "Hungary"), ("ID", "Indonesia"), ("IE", "Ireland"), ("IL", "Israel"), ("IM", "Isle of Man"),
+    ("IN", "India"), ("IO", "British Indian Ocean Territory"), ("IQ", "Iraq"), ("IR", "Iran"),
+    ("IS", "Iceland"), ("IT", "Italy"), ("JE", "Jersey"), ("JM", "Jamaica"), ("JO", "Jordan"),
+    ("JP", "Japan"), ("KE", "Kenya"), ("KG", "Kyrgyzstan"), ("KH", "Cambodia"), ("KI", "Kiribati"),
+    ("KM", "Comoros"), ("KN", "Saint Kitts and Nevis"), ("KP", "North Korea"), ("KR", "South Korea"),
+    ("KW", "Kuwait"), ("KY", "Cayman Islands"), ("KZ", "Kazakhstan"), ("LA", "Laos"), ("LB", "Lebanon"),
+    ("LC", "Saint Lucia"), ("LI", "Liechtenstein"), ("LK", "Sri Lanka"), ("LR", "Liberia"), ("LS", "Lesotho"),
+    ("LT", "Lithuania"), ("LU", "Luxembourg"), ("LV", "Latvia"), ("LY", "Libya"), ("MA", "Morocco"),
+    ("MC", "Monaco"), ("MD", "Moldova"), ("ME", "Montenegro"), ("MF", "Saint Martin"), ("MG", "Madagascar"),
+    ("MH", "Marshall Islands"), ("MK", "North Macedonia"), ("ML", "Mali"), ("MM", "Myanmar"),
+    ("MN", "Mongolia"), ("MO", "Macau"), ("MP", "Northern Mariana Islands"), ("MQ", "Martinique"),
+    ("MR", "Mauritania"), ("MS", "Montserrat"), ("MT", "Malta"), ("MU", "Mauritius"), ("MV", "Maldives"),
+    ("MW", "Malawi"), ("MX", "Mexico"), ("MY", "Malaysia"), ("MZ", "Mozambique"), ("NA", "Namibia"),
+    ("NC", "New Caledonia"), ("NE", "Niger"), ("NF", "Norfolk Island"), ("NG", "Nigeria"),
+    ("NI", "Nicaragua"), ("NL", "Netherlands"), ("NO", "Norway"), ("NP", "Nepal"), ("NR", "Nauru"),
+    ("NU", "Niue"), ("NZ", "New Zealand"), ("OM", "Oman"), ("PA", "Panama"), ("PE", "Peru"),
+    ("PF", "French Polynesia"), ("PG", "Papua New Guinea"), ("PH", "Philippines"), ("PK", "Pakistan"),
+    ("PL", "Poland"), ("PM", "Saint Pierre and Miquelon"), ("PN", "Pitcairn Islands"), ("PR", "Puerto Rico"),
+    ("PS", "Palestine"), ("PT", "Portugal"), ("PW", "Palau"), ("PY", "Paraguay"), ("QA", "Qatar"),
+    ("RE", "Réunion"), ("RO", "Romania"), ("RS", "Serbia"), ("RU", "Russia"), ("RW", "Rwanda"),
+    ("SA", "Saudi Arabia"), ("SB", "Solomon Islands"), ("SC", "Seychelles"), ("SD", "Sudan"),
+    ("SE", "Sweden"), ("SG", "Singapore"), ("SH", "Saint Helena"), ("SI", "Slovenia"),
+    ("SJ", "Svalbard and Jan Mayen"), ("SK", "Slovakia"), ("SL", "Sierra Leone"), ("SM", "San Marino"),
+    ("SN", "Senegal"), ("SO", "Somalia"), ("SR", "Suriname"), ("SS", "South Sudan"),
+    ("ST", "São Tomé and Príncipe"), ("SV", "El Salvador"), ("SX", "Sint Maarten"), ("SY", "Syria"),
+    ("SZ", "Eswatini"), ("TC", "Turks and Caicos Islands"), ("TD", "Chad"),
+    ("TF", "French Southern Territories"), ("TG", "Togo"), ("TH", "Thailand"), ("TJ", "Tajikistan"),
+    ("TK", "Tokelau"), ("TL", "Timor-Leste"), ("TM", "Turkmenistan"), ("TN", "Tunisia"), ("TO", "Tonga"),
+    ("TR", "Turkey"), ("TT", "Trinidad and Tobago"), ("TV", "Tuvalu"), ("TW", "Taiwan"), ("TZ", "Tanzania"),
+    ("UA", "Ukraine"), ("UG", "Uganda"), ("UM", "U.S. Minor Outlying Islands"), ("US", "United States"),
+    ("UY", "Uruguay"), ("UZ", "Uzbekistan"), ("VA", "Vatican City"),
+    ("VC", "Saint Vincent and the Grenadines"), ("VE", "Venezuela"), ("VG", "British Virgin Islands"),
+    ("VI", "U.S. Virgin Islands"), ("VN", "Vietnam"), ("VU", "Vanuatu"), ("WF", "Wallis and Futuna"),
+    ("WS", "Samoa"), ("YE", "Yemen"), ("YT", "Mayotte"), ("ZA", "South Africa"), ("ZM", "Zambia"),
+    ("ZW", "Zimbabwe"),
+];
+
+/// English and Pinyin names of the province-level divisions by GB/T 2260 code
+const PROVINCES: &[(u32, &str, &str)] = &[
+    (110000, "Beijing", "Beijing"), (120000, "Tianjin", "Tianjin"), (130000, "Hebei", "Hebei"),
+    (140000, "Shanxi", "Shanxi"), (150000, "Inner Mongolia", "Neimenggu"), (210000, "Liaoning", "Liaoning"),
+    (220000, "Jilin", "Jilin"), (230000, "Heilongjiang", "Heilongjiang"), (310000, "Shanghai", "Shanghai"),
+    (320000, "Jiangsu", "Jiangsu"), (330000, "Zhejiang", "Zhejiang"), (340000, "Anhui", "Anhui"),
+    (350000, "Fujian", "Fujian"), (360000, "Jiangxi", "Jiangxi"), (370000, "Shandong", "Shandong"),
+    (410000, "Henan", "Henan"), (420000, "Hubei", "Hubei"), (430000, "Hunan", "Hunan"),
+    (440000, "Guangdong", "Guangdong"), (450000, "Guangxi", "Guangxi"), (460000, "Hainan", "Hainan"),
+    (500000, "Chongqing", "Chongqing"), (510000, "Sichuan", "Sichuan"), (520000, "Guizhou", "Guizhou"),
+    (530000, "Yunnan", "Yunnan"), (540000, "Tibet", "Xizang"), (610000, "Shaanxi", "Shaanxi"),
+    (620000, "Gansu", "Gansu"), (630000, "Qinghai", "Qinghai"), (640000, "Ningxia", "Ningxia"),
+    (650000, "Xinjiang", "Xinjiang"), (710000, "Taiwan", "Taiwan"), (810000, "Hong Kong", "Xianggang"),
+    (820000, "Macau", "Aomen"),
+];
+
+/// English names of the prefectures known by a name other than their Pinyin
+const CITY_EXONYMS: &[(u32, &str)] = &[
+    (150100, "Hohhot"), (150600, "Ordos"), (150700, "Hulunbuir"), (150800, "Bayannur"), (150900, "Ulanqab"),
+    (152200, "Hinggan"), (152500, "Xilingol"), (152900, "Alxa"), (230100, "Harbin"), (230200, "Qiqihar"),
+    (513200, "Ngawa"), (513300, "Garze"), (540100, "Lhasa"), (540200, "Shigatse"), (540300, "Chamdo"),
+    (540400, "Nyingchi"), (540600, "Nagqu"), (542500, "Ngari"), (632600, "Golog"), (650100, "Urumqi"),
+    (650200, "Karamay"), (650400, "Turpan"), (652700, "Bortala"), (652800, "Bayingolin"), (652900, "Aksu"),
+    (653000, "Kizilsu"), (653100, "Kashgar"), (653200, "Hotan"), (654000, "Ili"), (654300, "Altay"),
+    (659002, "Aral"),
+];
+
+/// Traditional forms of the simplified characters found in place names,
+/// sorted by simplified character
+const TRADITIONAL: &[(char, char)] = &[
+    ('万', '萬'), ('专', '專'), ('业', '業'), ('丛', '叢'), ('东', '東'), ('丰', '豐'), ('临', '臨'), ('丽', '麗'), ('义', '義'), ('乌', '烏'),
+    ('乐', '樂'), ('乔', '喬'), ('乡', '鄉'), ('买', '買'), ('云', '雲'), ('亚', '亞'), ('产', '產'), ('仑', '崙'), ('仪', '儀'), ('众', '眾'),
+    ('会', '會'), ('伞', '傘'), ('伟', '偉'), ('伦', '倫'), ('侨', '僑'), ('兰', '蘭'), ('关', '關'), ('兴', '興'), ('兹', '茲'), ('内', '內'),
+    ('冈', '岡'), ('农', '農'), ('凉', '涼'), ('几', '幾'), ('凤', '鳳'), ('凯', '凱'), ('则', '則'), ('刚', '剛'), ('别', '別'), ('务', '務'),
+    ('劳', '勞'), ('区', '區'), ('华', '華'), ('卢', '盧'), ('卤', '鹵'), ('卫', '衛'), ('厂', '廠'), ('历', '歷'), ('厦', '廈'), ('县', '縣'),
+    ('双', '雙'), ('叙', '敘'), ('吕', '呂'), ('吴', '吳'), ('园', '園'), ('围', '圍'), ('国', '國'), ('图', '圖'), ('圆', '圓'), ('圣', '聖'),
+    ('场', '場'), ('坚', '堅'), ('坝', '壩'), ('壮', '壯'), ('头', '頭'), ('奥', '奧'), ('娄', '婁'), ('学', '學'), ('宁', '寧'), ('宝', '寶'),
+    ('宫', '宮'), ('宾', '賓'), ('寿', '壽'), ('尔', '爾'), ('尧', '堯'), ('层', '層'), ('属', '屬'), ('屿', '嶼'), ('岁', '歲'), ('岗', '崗'),
+    ('岚', '嵐'), ('岛', '島'), ('岭', '嶺'), ('峡', '峽'), ('帅', '帥'), ('广', '廣'), ('庄', '莊'), ('庆', '慶'), ('库', '庫'), ('开', '開'),
+    ('张', '張'), ('归', '歸'), ('怀', '懷'), ('扬', '揚'), ('抚', '撫'), ('挝', '撾'), ('无', '無'), ('时', '時'), ('晋', '晉'), ('机', '機'),
+    ('杂', '雜'), ('来', '來'), ('杨', '楊'), ('杰', '傑'), ('极', '極'), ('枣', '棗'), ('树', '樹'), ('桥', '橋'), ('楼', '樓'), ('欧', '歐'),
+    ('毕', '畢'), ('汇', '匯'), ('汉', '漢'), ('汤', '湯'), ('沟', '溝'), ('沧', '滄'), ('泸', '瀘'), ('泽', '澤'), ('泾', '涇'), ('洼', '窪'),
+    ('测', '測'), ('浍', '澮'), ('济', '濟'), ('淀', '澱'), ('渔', '漁'), ('温', '溫'), ('湾', '灣'), ('湿', '濕'), ('滦', '灤'), ('滨', '濱'),
+    ('潍', '濰'), ('潜', '潛'), ('灯', '燈'), ('灵', '靈'), ('灿', '燦'), ('炉', '爐'), ('点', '點'), ('烟', '煙'), ('热', '熱'), ('爱', '愛'),
+    ('爷', '爺'), ('状', '狀'), ('犹', '猶'), ('独', '獨'), ('狮', '獅'), ('狱', '獄'), ('猎', '獵'), ('献', '獻'), ('玛', '瑪'), ('环', '環'),
+    ('现', '現'), ('珲', '琿'), ('琼', '瓊'), ('电', '電'), ('画', '畫'), ('畅', '暢'), ('疗', '療'), ('盐', '鹽'), ('监', '監'), ('盖', '蓋'),
+    ('盘', '盤'), ('睑', '瞼'), ('矶', '磯'), ('码', '碼'), ('砖', '磚'), ('确', '確'), ('礼', '禮'), ('祯', '禎'), ('离', '離'), ('秃', '禿'),
+    ('种', '種'), ('积', '積'), ('称', '稱'), ('稳', '穩'), ('穷', '窮'), ('窝', '窩'), ('竞', '競'), ('笔', '筆'), ('筑', '築'), ('简', '簡'),
+    ('类', '類'), ('粮', '糧'), ('紧', '緊'), ('红', '紅'), ('约', '約'), ('级', '級'), ('纪', '紀'), ('纳', '納'), ('纵', '縱'), ('纽', '紐'),
+    ('线', '線'), ('组', '組'), ('织', '織'), ('终', '終'), ('绍', '紹'), ('经', '經'), ('结', '結'), ('给', '給'), ('绛', '絳'), ('统', '統'),
+    ('绥', '綏'), ('维', '維'), ('绵', '綿'), ('缅', '緬'), ('网', '網'), ('罗', '羅'), ('罢', '罷'), ('翘', '翹'), ('聂', '聶'), ('职', '職'),
+    ('联', '聯'), ('肃', '肅'), ('肠', '腸'), ('肤', '膚'), ('胜', '勝'), ('脑', '腦'), ('脱', '脫'), ('腊', '臘'), ('舰', '艦'), ('艺', '藝'),
+    ('节', '節'), ('芜', '蕪'), ('芦', '蘆'), ('苏', '蘇'), ('荆', '荊'), ('荡', '蕩'), ('荣', '榮'), ('药', '藥'), ('莱', '萊'), ('获', '獲'),
+    ('萝', '蘿'), ('营', '營'), ('萨', '薩'), ('蓝', '藍'), ('蓟', '薊'), ('虏', '虜'), ('虽', '雖'), ('补', '補'), ('见', '見'), ('观', '觀'),
+    ('规', '規'), ('视', '視'), ('览', '覽'), ('觉', '覺'), ('计', '計'), ('认', '認'), ('讯', '訊'), ('记', '記'), ('许', '許'), ('设', '設'),
+    ('证', '證'), ('评', '評'), ('识', '識'), ('译', '譯'), ('试', '試'), ('话', '話'), ('诞', '誕'), ('询', '詢'), ('该', '該'), ('详', '詳'),
+    ('语', '語'), ('说', '說'), ('请', '請'), ('诺', '諾'), ('读', '讀'), ('调', '調'), ('贝', '貝'), ('负', '負'), ('贡', '貢'), ('财', '財'),
+    ('质', '質'), ('购', '購'), ('贴', '貼'), ('贵', '貴'), ('贸', '貿'), ('费', '費'), ('贺', '賀'), ('资', '資'), ('赞', '贊'), ('赣', '贛'),
+    ('车', '車'), ('轩', '軒'), ('转', '轉'), ('轮', '輪'), ('软', '軟'), ('轻', '輕'), ('载', '載'), ('辉', '輝'), ('输', '輸'), ('辖', '轄'),
+    ('边', '邊'), ('辽', '遼'), ('达', '達'), ('迁', '遷'), ('过', '過'), ('运', '運'), ('还', '還'), ('进', '進'), ('远', '遠'), ('连', '連'),
+    ('适', '適'), ('选', '選'), ('递', '遞'), ('邮', '郵'), ('邻', '鄰'), ('郑', '鄭'), ('郸', '鄲'), ('释', '釋'), ('钟', '鍾'), ('钢', '鋼'),
+    ('钦', '欽'), ('钱', '錢'), ('铁', '鐵'), ('铜', '銅'), ('银', '銀'), ('铺', '鋪'), ('链', '鏈'), ('销', '銷'), ('锁', '鎖'), ('锋', '鋒'),
+    ('错', '錯'), ('锡', '錫'), ('锦', '錦'), ('键', '鍵'), ('镇', '鎮'), ('长', '長'), ('门', '門'), ('闵', '閔'), ('闸', '閘'), ('闻', '聞'),
+    ('阁', '閣'), ('队', '隊'), ('阳', '陽'), ('阴', '陰'), ('阶', '階'), ('际', '際'), ('陆', '陸'), ('陇', '隴'), ('陈', '陳'), ('陕', '陝'),
+    ('险', '險'), ('随', '隨'), ('难', '難'), ('雾', '霧'), ('静', '靜'), ('鞑', '韃'), ('韦', '韋'), ('韩', '韓'), ('顶', '頂'), ('顺', '順'),
+    ('顾', '顧'), ('顿', '頓'), ('预', '預'), ('领', '領'), ('颇', '頗'), ('频', '頻'), ('题', '題'), ('颠', '顛'), ('风', '風'), ('飞', '飛'),
+    ('饭', '飯'), ('饶', '饒'), ('馆', '館'), ('马', '馬'), ('驻', '駐'), ('驿', '驛'), ('验', '驗'), ('鱼', '魚'), ('鲁', '魯'), ('鲜', '鮮'),
+    ('鸟', '鳥'), ('鸠', '鳩'), ('鸡', '雞'), ('鸣', '鳴'), ('鸭', '鴨'), ('鹅', '鵝'), ('鹏', '鵬'), ('鹤', '鶴'), ('鹰', '鷹'), ('麦', '麥'),
+    ('黄', '黃'), ('黾', '黽'), ('齐', '齊'), ('齿', '齒'), ('龙', '龍'), ('龟', '龜'),
+];
+
+/// Words whose characters convert differently from the character table
+const TRADITIONAL_WORDS: &[(&str, &str)] = &[("沈阳", "瀋陽")];
+
+impl Location {
+    /// Render the place names in `language`
+    ///
+    /// The area, country, province and city are replaced when the tables
+    /// cover them; Traditional Chinese also converts the district. Names
+    /// without an entry, the ISP and the codes are kept as they are.
+    pub fn localized(mut self, language: Language) -> Self {
+        match language {
+            Language::Chinese => {}
+            Language::TraditionalChinese => {
+                let names = [&mut self.area, &mut self.country, &mut self.province, &mut self.city, &mut self.district];
+                for name in names.into_iter().flatten() {
+                    *name = traditional(name);
+                }
+            }
+            Language::English | Language::Pinyin => {
+                let pinyin = language == Language::Pinyin;
+                if let Some(area) = &mut self.area {
+                    if let Some(&(_, english)) = AREAS.iter().find(|&&(name, _)| name == area) {
+                        *area = english.to_string();
+                    }
+                }
+                let country = match self.country_code {
+                    Some("CN") if pinyin => Some("Zhongguo"),
+                    Some(code) => COUNTRIES.iter().find(|&&(c, _)| c == code).map(|&(_, name)| name),
+                    None => None,
+                };
+                if let Some(country) = country {
+                    self.country = Some(country.to_string());
+                }
+                if let Some(province) = self.province_code.and_then(|code| province_name(code, pinyin)) {
+                    self.province = Some(province.to_string());
+                }
+                if let Some(city) = self.city_code.and_then(|code| city_name(code, pinyin)) {
+                    self.city = Some(city.to_string());
+                }
+            }
+        }
+        self
+    }
+}
+
+/// English or Pinyin name of a province-level division
+fn province_name(code: u32, pinyin: bool) -> Option<&'static str> {
+    PROVINCES.iter().find(|&&(c, ..)| c == code).map(|&(_, english, py)| if pinyin { py } else { english })
+}
+
+/// English or Pinyin name of a prefecture-level city
+fn city_name(code: u32, pinyin: bool) -> Option<&'static str> {
+    let exonym = CITY_EXONYMS.iter().find(|&&(c, _)| c == code).filter(|_| !pinyin);
+    if let Some(&(_, english)) = exonym {
+        return Some(english);
+    }
+    let city = AdminDivision::from_code(code)?;
+    // The urban districts of a municipality carry its name
+    city.pinyin().or_else(|| province_name(city.code / 10000 * 10000, pinyin))
+}
+
+/// Convert simplified characters to traditional ones
+fn traditional(text: &str) -> String {
+    let mut converted = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if let Some(&(word, traditional)) = TRADITIONAL_WORDS.iter().find(|&&(word, _)| rest.starts_with(word)) {
+            converted.push_str(traditional);
+            rest = &rest[word.len()..];
+            continue;
+        }
+        let index = TRADITIONAL.binary_search_by_key(&c, |&(simplified, _)| simplified);
+        converted.push(index.map_or(c, |i| TRADITIONAL[i].1));
+        rest = &rest[c.len_utf8()..];
+    }
+    converted
+}
@@ -0,0 +1,110 @@
+//! Prefix and range queries
+//!
+//! [`DbSearcher::search_prefix`] and [`DbSearcher::search_range`] return every
+//! database range overlapping a block of addresses. They binary search the
+//! index for the first overlapping record and read on from there, so a /8
+//! costs as much as the number of ranges it spans, not its addresses.
+
+use super::{CzdbError, DbSearcher, IpType};
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// A database range overlapping a queried block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeOverlap {
+    /// First address of the database range, which may precede the block
+    pub start: IpAddr,
+    /// Last address of the database range, inclusive, which may follow the block
+    pub end: IpAddr,
+    pub region: Arc<str>,
+    /// Number of addresses of the block inside the range, saturating at
+    /// `u128::MAX` for a range covering all of `::/0`
+    pub overlap: u128,
+}
+
+impl DbSearcher {
+    /// Every range overlapping a CIDR prefix such as `1.0.0.0/16`, in address order
+    ///
+    /// Host bits are ignored, so `1.0.0.1/16` is `1.0.0.0/16`; a bare address
+    /// is a single-address prefix. Addresses no range covers are absent from
+    /// the result, so the overlaps sum to less than the prefix size. A prefix
+    /// is homogeneous when a single range covers all of it.
+    pub fn search_prefix(&self, prefix: &str) -> Result<Vec<RangeOverlap>, CzdbError> {
+        let (addr, len) = match prefix.trim().split_once('/') {
+            Some((addr, len)) => (IpAddr::from_str(addr)?, Some(len)),
+            None => (IpAddr::from_str(prefix.trim())?, None),
+        };
+        let (value, bits) = self.address_value(addr)?;
+        let len = match len {
+            Some(len) => len
+                .parse::<u32>()
+                .ok()
+                .filter(|&len| len <= bits)
+                .ok_or_else(|| CzdbError::InvalidOption(format!("invalid prefix length {len:?} in {prefix:?}")))?,
+            None => bits,
+        };
+        // Bits of the address below the prefix, within the 128-bit value
+        let host = u128::MAX.checked_shr(128 - bits + len).unwrap_or(0);
+        self.overlapping(value & !host, value | host)
+    }
+
+    /// Every range overlapping the addresses from `start` to `end` inclusive, in address order
+    pub fn search_range(&self, start: &str, end: &str) -> Result<Vec<RangeOverlap>, CzdbError> {
+        let (first, _) = self.address_value(IpAddr::from_str(start.trim())?)?;
+        let (last, _) = self.address_value(IpAddr::from_str(end.trim())?)?;
+        if first > last {
+            return Err(CzdbError::InvalidOption(format!("range start {start} is after its end {end}")));
+        }
+        self.overlapping(first, last)
+    }
+
+    /// Numeric value and bit length of an address of the database's family
+    fn address_value(&self, addr: IpAddr) -> Result<(u128, u32), CzdbError> {
+        match (self.ip_type, addr) {
+            (IpType::Ipv4, IpAddr::V4(addr)) => Ok((u32::from(addr) as u128, 32)),
+            (IpType::Ipv6, IpAddr::V6(addr)) => Ok((u128::from(addr), 128)),
+            _ => Err(CzdbError::InvalidIpType),
+        }
+    }
+
+    fn overlapping(&self, first: u128, last: u128) -> Result<Vec<RangeOverlap>, CzdbError> {
+        let index_bytes = self.index_bytes()?;
+        let count = index_bytes.len() / self.record_len;
+        let record = |i: usize| self.parse_record(&index_bytes[i * self.record_len..(i + 1) * self.record_len]);
+
+        // First record not ending before the block
+        let (mut lo, mut hi) = (0, count);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if record(mid).end < first {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+
+        let mut overlaps = Vec::new();
+        for i in lo..count {
+            if record(i).start > last {
+                break;
+            }
+            let record = self.checked_record(&index_bytes[i * self.record_len..(i + 1) * self.record_len], i)?;
+            // Only out-of-order records end before the block here
+            if record.end < first {
+                continue;
+            }
+            let region: Arc<str> = match self.region_table.as_ref().and_then(|t| t.get(record.data_ptr, record.data_len)) {
+                Some(region) => Arc::clone(region),
+                None => self.get_region(record.data_ptr, record.data_len)?.into(),
+            };
+            overlaps.push(RangeOverlap {
+                start: self.to_ip_addr(record.start),
+                end: self.to_ip_addr(record.end),
+                region,
+                overlap: (record.end.min(last) - record.start.max(first)).saturating_add(1),
+            });
+        }
+        Ok(overlaps)
+    }
+}
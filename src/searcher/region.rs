@@ -0,0 +1,338 @@
+//! Structured region parsing
+//!
+//! CZ88 region text is the geo hierarchy, levels joined by en dashes, followed
+//! by the ISP after a tab: `中国–福建–福州\t电信`. [`Location::parse`] splits it
+//! into typed fields, and [`DbSearcher::search_location`] applies it to a lookup.
+//! Older files concatenate the Chinese levels (`福建省福州市`) or join them with
+//! ASCII hyphens, and separate the ISP with a space; both are accepted for text
+//! starting with a Chinese place name, as foreign names contain hyphens and
+//! spaces (`Winston-Salem`, `New York`).
+
+use super::division::{CHONGQING, CHONGQING_COUNTIES};
+use super::iso::cn_subdivision;
+use super::{AdminDivision, Carrier, Country, CzdbError, DbSearcher, IspCategory, Language};
+
+/// Separators between hierarchy levels
+const LEVEL_SEPARATORS: &[char] = &['–', '—'];
+
+/// Separator between hierarchy levels of older Chinese text
+const LEGACY_SEPARATOR: char = '-';
+
+/// Labels CZ88 uses in place of a country: continents, regions and special address blocks
+const AREAS: &[&str] = &[
+    "亚洲", "欧洲", "非洲", "北美洲", "南美洲", "大洋洲", "南极洲",
+    "亚太地区", "北美地区", "欧洲地区", "非洲地区", "拉美地区", "中东地区",
+    "IANA", "未分配地址", "保留地址", "共享地址", "组播地址", "局域网", "本机地址", "本地回环",
+];
+
+/// Kind of a Chinese province-level division
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DivisionKind {
+    /// 省
+    Province,
+    /// 直辖市: 北京, 天津, 上海, 重庆
+    Municipality,
+    /// 自治区: 内蒙古, 广西, 西藏, 宁夏, 新疆
+    AutonomousRegion,
+    /// 特别行政区: 香港, 澳门
+    SpecialAdministrativeRegion,
+}
+
+/// Province-level divisions by short name, with their full name and GB/T 2260 code
+pub(super) const DIVISIONS: &[(&str, &str, DivisionKind, u32)] = &[
+    ("北京", "北京市", DivisionKind::Municipality, 110000),
+    ("天津", "天津市", DivisionKind::Municipality, 120000),
+    ("上海", "上海市", DivisionKind::Municipality, 310000),
+    ("重庆", "重庆市", DivisionKind::Municipality, 500000),
+    ("河北", "河北省", DivisionKind::Province, 130000),
+    ("山西", "山西省", DivisionKind::Province, 140000),
+    ("辽宁", "辽宁省", DivisionKind::Province, 210000),
+    ("吉林", "吉林省", DivisionKind::Province, 220000),
+    ("黑龙江", "黑龙江省", DivisionKind::Province, 230000),
+    ("江苏", "江苏省", DivisionKind::Province, 320000),
+    ("浙江", "浙江省", DivisionKind::Province, 330000),
+    ("安徽", "安徽省", DivisionKind::Province, 340000),
+    ("福建", "福建省", DivisionKind::Province, 350000),
+    ("江西", "江西省", DivisionKind::Province, 360000),
+    ("山东", "山东省", DivisionKind::Province, 370000),
+    ("河南", "河南省", DivisionKind::Province, 410000),
+    ("湖北", "湖北省", DivisionKind::Province, 420000),
+    ("湖南", "湖南省", DivisionKind::Province, 430000),
+    ("广东", "广东省", DivisionKind::Province, 440000),
+    ("海南", "海南省", DivisionKind::Province, 460000),
+    ("四川", "四川省", DivisionKind::Province, 510000),
+    ("贵州", "贵州省", DivisionKind::Province, 520000),
+    ("云南", "云南省", DivisionKind::Province, 530000),
+    ("陕西", "陕西省", DivisionKind::Province, 610000),
+    ("甘肃", "甘肃省", DivisionKind::Province, 620000),
+    ("青海", "青海省", DivisionKind::Province, 630000),
+    ("台湾", "台湾省", DivisionKind::Province, 710000),
+    ("内蒙古", "内蒙古自治区", DivisionKind::AutonomousRegion, 150000),
+    ("广西", "广西壮族自治区", DivisionKind::AutonomousRegion, 450000),
+    ("西藏", "西藏自治区", DivisionKind::AutonomousRegion, 540000),
+    ("宁夏", "宁夏回族自治区", DivisionKind::AutonomousRegion, 640000),
+    ("新疆", "新疆维吾尔自治区", DivisionKind::AutonomousRegion, 650000),
+    ("香港", "香港特别行政区", DivisionKind::SpecialAdministrativeRegion, 810000),
+    ("澳门", "澳门特别行政区", DivisionKind::SpecialAdministrativeRegion, 820000),
+];
+
+/// Suffixes that end a city name in concatenated text
+const CITY_SUFFIXES: &[&str] = &["市", "自治州", "地区", "盟"];
+
+/// Region text split into its hierarchy and ISP
+///
+/// Chinese province-level divisions use their short name (`福建`, `内蒙古`,
+/// `香港`) whatever form the database stores. Levels below the district are
+/// dropped.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Location {
+    /// Continent, region or special address block given instead of a
+    /// country, such as `亚太地区` or `局域网`
+    pub area: Option<String>,
+    pub country: Option<String>,
+    /// Province, state or other first-level division
+    pub province: Option<String>,
+    /// Kind of a Chinese province-level division
+    pub division: Option<DivisionKind>,
+    pub city: Option<String>,
+    pub district: Option<String>,
+    pub isp: Option<String>,
+    /// GB/T 2260 code of the Chinese province-level division, e.g. 440000
+    pub province_code: Option<u32>,
+    /// GB/T 2260 code of the Chinese prefecture-level city, e.g. 440100; for
+    /// municipalities the code of their urban districts, e.g. 110100, or
+    /// 500200 for the counties of 重庆
+    pub city_code: Option<u32>,
+    /// ISO 3166-1 alpha-2 code of the country, e.g. `US`
+    pub country_code: Option<&'static str>,
+    /// ISO 3166-1 alpha-3 code of the country, e.g. `USA`
+    pub country_alpha3: Option<&'static str>,
+    /// ISO 3166-2:CN code of the Chinese province-level division, e.g. `CN-GD`
+    pub subdivision_code: Option<&'static str>,
+    /// Canonical carrier the ISP names, e.g. `chinatelecom`
+    pub carrier: Option<Carrier>,
+    /// Kind of network the ISP describes; `Unknown` without an ISP
+    pub isp_category: IspCategory,
+}
+
+impl DivisionKind {
+    /// Short name, full name, kind and code of a province-level division
+    /// named either way (`福建` or `福建省`)
+    pub(super) fn lookup(name: &str) -> Option<(&'static str, &'static str, DivisionKind, u32)> {
+        DIVISIONS.iter().copied().find(|&(short, full, kind, _)| {
+            name == short
+                || name == full
+                || name.strip_prefix(short).is_some_and(|suffix| match kind {
+                    DivisionKind::Province => suffix == "省",
+                    DivisionKind::Municipality => suffix == "市",
+                    DivisionKind::AutonomousRegion => suffix == "自治区",
+                    DivisionKind::SpecialAdministrativeRegion => suffix == "特别行政区",
+                })
+        })
+    }
+
+    /// Province-level division at the start of concatenated text, and the
+    /// length of the name it was written with
+    fn prefix(text: &str) -> Option<(&'static str, DivisionKind, usize)> {
+        DIVISIONS
+            .iter()
+            .flat_map(|&(short, full, kind, _)| [(short, full, kind), (short, short, kind)])
+            .filter(|&(_, name, _)| text.starts_with(name))
+            .max_by_key(|&(_, name, _)| name.len())
+            .map(|(short, name, kind)| (short, kind, name.len()))
+    }
+}
+
+/// Flat fields of the structured record the WASM and Node.js bindings
+/// return, empty where a level or code is missing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RecordFields<'a> {
+    /// Country, or the area of regions naming none
+    pub country: &'a str,
+    pub province: &'a str,
+    pub city: &'a str,
+    pub isp: &'a str,
+    pub country_code: &'static str,
+    pub country_alpha3: &'static str,
+    pub subdivision_code: &'static str,
+    pub carrier: &'static str,
+    pub isp_category: &'static str,
+}
+
+impl Location {
+    /// Fields of the bindings' structured record
+    pub(crate) fn record_fields(&self) -> RecordFields<'_> {
+        RecordFields {
+            country: self.country.as_deref().or(self.area.as_deref()).unwrap_or(""),
+            province: self.province.as_deref().unwrap_or(""),
+            city: self.city.as_deref().unwrap_or(""),
+            isp: self.isp.as_deref().unwrap_or(""),
+            country_code: self.country_code.unwrap_or(""),
+            country_alpha3: self.country_alpha3.unwrap_or(""),
+            subdivision_code: self.subdivision_code.unwrap_or(""),
+            carrier: self.carrier.map_or("", |carrier| carrier.id),
+            isp_category: self.isp_category.as_str(),
+        }
+    }
+
+    /// Parse region text as returned by [`DbSearcher::search`]
+    pub fn parse(region: &str) -> Self {
+        let mut location = Self::parse_levels(region);
+        if let Some(country) = location.country.as_deref().and_then(Country::from_name) {
+            location.country_code = Some(country.alpha2);
+            location.country_alpha3 = Some(country.alpha3);
+        }
+        location.subdivision_code = location.province_code.and_then(cn_subdivision);
+        if let Some(isp) = &location.isp {
+            location.carrier = Carrier::from_isp(isp);
+            location.isp_category = IspCategory::classify(isp);
+        }
+        location
+    }
+
+    /// Split the hierarchy and ISP, with GB/T 2260 codes
+    fn parse_levels(region: &str) -> Self {
+        let (geo, isp) = split_isp(region);
+        let mut location = Location { isp, ..Location::default() };
+
+        let mut levels: Vec<&str> = geo.split(LEVEL_SEPARATORS).map(str::trim).filter(|l| !l.is_empty()).collect();
+        if let [single] = levels[..] {
+            if is_legacy(single) {
+                levels = single.split(LEGACY_SEPARATOR).map(str::trim).filter(|l| !l.is_empty()).collect();
+            }
+            if let [single] = levels[..] {
+                levels = split_concatenated(single);
+            }
+        }
+        let mut levels = levels.into_iter();
+
+        let mut next = levels.next();
+        if let Some(area) = next.filter(|level| AREAS.contains(level)) {
+            location.area = Some(area.to_string());
+            next = levels.next();
+        }
+        let Some(first) = next else {
+            return location;
+        };
+
+        // CZ88 lists every province-level division under 中国; some sources omit the country
+        let province = if first == "中国" || first == "中华人民共和国" {
+            location.country = Some("中国".to_string());
+            levels.next()
+        } else if DivisionKind::lookup(first).is_some() {
+            location.country = Some("中国".to_string());
+            Some(first)
+        } else {
+            location.country = Some(first.to_string());
+            levels.next()
+        };
+        let Some(mut province) = province else {
+            return location;
+        };
+
+        let mut city = None;
+        let division = if location.country.as_deref() == Some("中国") {
+            // `宁夏中卫`: a city written together with its province
+            if let (None, Some((_, _, len))) = (DivisionKind::lookup(province), DivisionKind::prefix(province)) {
+                if province[len..].chars().count() >= 2 {
+                    (province, city) = (&province[..len], Some(&province[len..]));
+                }
+            }
+            DivisionKind::lookup(province)
+        } else {
+            None
+        };
+        location.province = Some(division.map_or(province, |(short, ..)| short).to_string());
+        location.division = division.map(|(_, _, kind, _)| kind);
+
+        let city = city.or_else(|| levels.next());
+        // `中国–北京–海淀区`: municipalities may skip the city level
+        let skips_city = matches!(division, Some((short, full, DivisionKind::Municipality, _))
+            if city.is_some_and(|c| c != short && c != full && (c.ends_with('区') || c.ends_with('县'))));
+        if skips_city {
+            location.city = division.map(|(short, ..)| short.to_string());
+            location.district = city.map(str::to_string);
+        } else {
+            location.city = city.map(str::to_string);
+            location.district = levels.next().map(str::to_string);
+        }
+
+        if let Some((.., code)) = division {
+            location.province_code = Some(code);
+            location.city_code = location.city.as_deref().and_then(|city| AdminDivision::city_in(code, city)).map(|d| d.code);
+            // 重庆 groups its counties apart from its urban districts
+            if code == CHONGQING && location.district.as_deref().is_some_and(|d| d.ends_with('县')) {
+                location.city_code = location.city_code.map(|_| CHONGQING_COUNTIES);
+            }
+        }
+        location
+    }
+}
+
+/// Whether text is in the older Chinese form: a single level starting with
+/// the country or a province-level division, such as `福建省福州市` or `中国-福建`
+fn is_legacy(text: &str) -> bool {
+    !text.contains(LEVEL_SEPARATORS) && (text.starts_with("中国") || DivisionKind::prefix(text).is_some())
+}
+
+/// Split off the ISP: after the first tab, or for older Chinese text without
+/// a tab after the first space
+fn split_isp(region: &str) -> (&str, Option<String>) {
+    let legacy = || region.trim().split_once(' ').filter(|(geo, _)| is_legacy(geo));
+    let (geo, rest) = match region.split_once('\t').or_else(legacy) {
+        Some((geo, rest)) => (geo, rest),
+        None => return (region.trim(), None),
+    };
+    let isp: Vec<&str> = rest.split(['\t', ' ']).filter(|s| !s.is_empty()).collect();
+    (geo.trim(), (!isp.is_empty()).then(|| isp.join(" ")))
+}
+
+/// Split concatenated Chinese text such as `中国福建省福州市鼓楼区` into levels;
+/// anything else is returned as a single level
+fn split_concatenated(text: &str) -> Vec<&str> {
+    let mut levels = Vec::new();
+    let mut rest = text;
+    if let Some(after) = rest.strip_prefix("中国").filter(|after| !after.is_empty()) {
+        levels.push("中国");
+        rest = after;
+    }
+    let Some((_, kind, len)) = DivisionKind::prefix(rest) else {
+        levels.push(rest);
+        return levels;
+    };
+    levels.push(&rest[..len]);
+    rest = &rest[len..];
+
+    // Municipalities go straight to the district
+    if kind != DivisionKind::Municipality {
+        let city_end = CITY_SUFFIXES
+            .iter()
+            .filter_map(|suffix| rest.find(suffix).map(|pos| pos + suffix.len()))
+            .min()
+            .filter(|&end| end < rest.len());
+        if let Some(end) = city_end {
+            levels.push(&rest[..end]);
+            rest = &rest[end..];
+        }
+    }
+    if !rest.is_empty() {
+        levels.push(rest);
+    }
+    levels
+}
+
+impl DbSearcher {
+    /// Search and parse the region into a [`Location`], or `None` when no
+    /// range covers the address
+    ///
+    /// Place names are in the language of [`SearchOptions::language`](super::SearchOptions::language).
+    /// [`DbSearcher::search`] is unaffected; this is the opt-in structured lookup.
+    pub fn search_location(&self, ip: &str) -> Result<Option<Location>, CzdbError> {
+        self.search_location_in(ip, self.language)
+    }
+
+    /// [`DbSearcher::search_location`] with place names in `language`
+    pub fn search_location_in(&self, ip: &str, language: Language) -> Result<Option<Location>, CzdbError> {
+        Ok(self.search_shared(ip)?.map(|region| Location::parse(&region).localized(language)))
+    }
+}
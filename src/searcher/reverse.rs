@@ -0,0 +1,213 @@
+//! Reverse lookup
+//!
+//! [`DbSearcher::search_region`] answers the opposite question of `search`:
+//! which ranges belong to a region. The first reverse lookup decodes and
+//! parses every distinct region once and groups the index records by region;
+//! later lookups only test the distinct regions against the filter.
+
+use super::{CzdbError, DbSearcher, DivisionKind, IndexRecord, Location};
+use ipnet::IpNet;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+
+/// How [`RegionFilter`] compares names
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum RegionMatch {
+    /// The name equals the filter value
+    #[default]
+    Exact,
+    /// The name starts with the filter value, so `电信` also matches
+    /// `电信/数据上网公共出口`
+    Prefix,
+}
+
+/// Region predicate of a reverse lookup
+///
+/// Fields left `None` match anything, so the default filter matches every
+/// range. Names are compared with the Chinese text of the database, as parsed
+/// by [`Location::parse`]; like it, the filter takes a Chinese province-level
+/// division by short or full name (`浙江` or `浙江省`) and `中华人民共和国` for
+/// `中国`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RegionFilter {
+    /// Country, or the area of regions naming none, e.g. `中国`, `亚太地区`
+    pub country: Option<String>,
+    pub province: Option<String>,
+    pub city: Option<String>,
+    pub isp: Option<String>,
+    pub matching: RegionMatch,
+}
+
+impl RegionFilter {
+    /// Whether a parsed region satisfies every field of the filter
+    pub fn matches(&self, location: &Location) -> bool {
+        let field = |want: Option<&str>, have: &Option<String>| match (want, have) {
+            (None, _) => true,
+            (Some(_), None) => false,
+            (Some(want), Some(have)) => match self.matching {
+                RegionMatch::Exact => have == want,
+                RegionMatch::Prefix => have.starts_with(want),
+            },
+        };
+        // Short names, as `Location::parse` stores them
+        let want_country = self.country.as_deref().map(|c| if c == "中华人民共和国" { "中国" } else { c });
+        let want_province = self.province.as_deref().map(|p| DivisionKind::lookup(p).map_or(p, |(short, ..)| short));
+
+        let country = if location.country.is_some() { &location.country } else { &location.area };
+        field(want_country, country)
+            && field(want_province, &location.province)
+            && field(self.city.as_deref(), &location.city)
+            && field(self.isp.as_deref(), &location.isp)
+    }
+}
+
+/// A database range returned by a reverse lookup
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegionRange {
+    pub start: IpAddr,
+    /// Last address of the range, inclusive
+    pub end: IpAddr,
+    pub region: Arc<str>,
+}
+
+/// Distinct regions with the positions of the index records pointing to them
+#[derive(Debug, Default)]
+pub(super) struct ReverseIndex {
+    regions: Vec<(Arc<str>, Location)>,
+    records: Vec<Vec<u32>>,
+}
+
+impl DbSearcher {
+    /// Every range whose region matches `filter`, in address order
+    ///
+    /// The first call reads the whole index and decodes every region, costing
+    /// about as much as building a Memory mode searcher; the result is kept
+    /// for later calls.
+    pub fn search_region(&self, filter: &RegionFilter) -> Result<Vec<RegionRange>, CzdbError> {
+        let index = self.reverse_index()?;
+        Ok(self
+            .matching_records(filter)?
+            .into_iter()
+            .map(|(record, region)| RegionRange {
+                start: self.to_ip_addr(record.start),
+                end: self.to_ip_addr(record.end),
+                region: Arc::clone(&index.regions[region as usize].0),
+            })
+            .collect())
+    }
+
+    /// The fewest CIDR blocks covering exactly the ranges whose region matches `filter`
+    ///
+    /// Adjacent ranges are merged first, so two neighbouring /25 ranges of the
+    /// same or different matching regions become one /24.
+    pub fn region_cidrs(&self, filter: &RegionFilter) -> Result<Vec<IpNet>, CzdbError> {
+        let records = self.matching_records(filter)?;
+        Ok(self.aggregate(records.into_iter().map(|(record, _)| (record.start, record.end))))
+    }
+
+    /// Index records matching `filter` with their region ids, in index order
+    ///
+    /// Building the reverse index checked that every record starts before it ends.
+    pub(super) fn matching_records(&self, filter: &RegionFilter) -> Result<Vec<(IndexRecord, u32)>, CzdbError> {
+        let index = self.reverse_index()?;
+        let mut positions: Vec<(u32, u32)> = Vec::new();
+        for (region, (_, location)) in index.regions.iter().enumerate() {
+            if filter.matches(location) {
+                positions.extend(index.records[region].iter().map(|&position| (position, region as u32)));
+            }
+        }
+        positions.sort_unstable();
+
+        let index_bytes = self.index_bytes()?;
+        Ok(positions
+            .into_iter()
+            .map(|(position, region)| {
+                let offset = position as usize * self.record_len;
+                (self.parse_record(&index_bytes[offset..offset + self.record_len]), region)
+            })
+            .collect())
+    }
+
+    /// The reverse index, built on first use
+    fn reverse_index(&self) -> Result<&ReverseIndex, CzdbError> {
+        if let Some(index) = self.reverse_index.get() {
+            return Ok(index);
+        }
+        let index = self.build_reverse_index()?;
+        // Another thread may have won the race; both built the same index
+        Ok(self.reverse_index.get_or_init(|| index))
+    }
+
+    fn build_reverse_index(&self) -> Result<ReverseIndex, CzdbError> {
+        let mut index = ReverseIndex::default();
+        let mut ids: HashMap<(usize, usize), u32> = HashMap::new();
+        let mut by_value: HashMap<Arc<str>, u32> = HashMap::new();
+
+        for (position, bytes) in self.index_bytes()?.chunks_exact(self.record_len).enumerate() {
+            let record = self.checked_record(bytes, position)?;
+            let key = (record.data_ptr, record.data_len);
+            let id = match ids.get(&key) {
+                Some(&id) => id,
+                None => {
+                    let region = self.get_region(record.data_ptr, record.data_len)?;
+                    let id = match by_value.get(region.as_str()) {
+                        Some(&id) => id,
+                        None => {
+                            let id = index.regions.len() as u32;
+                            let shared: Arc<str> = region.into();
+                            by_value.insert(Arc::clone(&shared), id);
+                            index.regions.push((Arc::clone(&shared), Location::parse(&shared)));
+                            index.records.push(Vec::new());
+                            id
+                        }
+                    };
+                    ids.insert(key, id);
+                    id
+                }
+            };
+            index.records[id as usize].push(position as u32);
+        }
+
+        Ok(index)
+    }
+
+    /// Merge inclusive (start, end) ranges and split them into the fewest CIDR blocks
+    pub(super) fn aggregate(&self, ranges: impl IntoIterator<Item = (u128, u128)>) -> Vec<IpNet> {
+        let bits = self.ip_bytes_len as u32 * 8;
+        let mut blocks = Vec::new();
+        for (mut start, end) in merge_ranges(ranges) {
+            loop {
+                // Largest block aligned at `start` that does not pass `end`
+                let aligned = if start == 0 { bits } else { start.trailing_zeros().min(bits) };
+                let span = end - start;
+                let fits = if span == u128::MAX { 128 } else { 127 - (span + 1).leading_zeros() };
+                let size = aligned.min(fits);
+                let prefix_len = (bits - size) as u8;
+                blocks.push(IpNet::new(self.to_ip_addr(start), prefix_len).expect("prefix length within the address"));
+
+                let last = start | u128::MAX.checked_shr(128 - size).unwrap_or(0);
+                if last >= end {
+                    break;
+                }
+                start = last + 1;
+            }
+        }
+        blocks
+    }
+}
+
+/// Sort inclusive (start, end) ranges and merge the overlapping and adjacent ones
+pub(super) fn merge_ranges(ranges: impl IntoIterator<Item = (u128, u128)>) -> Vec<(u128, u128)> {
+    let mut ranges: Vec<(u128, u128)> = ranges.into_iter().collect();
+    ranges.sort_unstable();
+
+    let mut merged: Vec<(u128, u128)> = Vec::new();
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1.saturating_add(1) => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
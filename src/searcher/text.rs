@@ -0,0 +1,116 @@
+//! Region text encodings
+//!
+//! Current CZDB files store UTF-8, older and third-party ones GBK/GB18030.
+//! The encoding is either given in [`SearchOptions`](super::SearchOptions) or
+//! detected from the geo map and the region records when the searcher is built.
+
+use super::DbSearcher;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::io::Cursor;
+
+/// Text encoding of the region strings in a database
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextEncoding {
+    /// Detect from the geo map and the trailing strings of the region records:
+    /// UTF-8 when every string is valid UTF-8, otherwise GB18030 when every
+    /// string is valid GB18030
+    #[default]
+    Auto,
+    Utf8,
+    /// GBK, decoded as its superset GB18030
+    Gbk,
+    Gb18030,
+}
+
+impl std::fmt::Display for TextEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            TextEncoding::Auto => "auto-detected",
+            TextEncoding::Utf8 => "UTF-8",
+            TextEncoding::Gbk => "GBK",
+            TextEncoding::Gb18030 => "GB18030",
+        })
+    }
+}
+
+impl TextEncoding {
+    /// Decode `bytes`; invalid sequences become U+FFFD, or `None` when `strict`
+    pub(super) fn decode(self, bytes: &[u8], strict: bool) -> Option<Cow<'_, str>> {
+        let encoding = match self {
+            TextEncoding::Auto | TextEncoding::Utf8 => {
+                return if strict {
+                    std::str::from_utf8(bytes).ok().map(Cow::Borrowed)
+                } else {
+                    Some(String::from_utf8_lossy(bytes))
+                };
+            }
+            TextEncoding::Gbk => encoding_rs::GBK,
+            TextEncoding::Gb18030 => encoding_rs::GB18030,
+        };
+        if strict {
+            encoding.decode_without_bom_handling_and_without_replacement(bytes)
+        } else {
+            Some(encoding.decode_without_bom_handling(bytes).0)
+        }
+    }
+
+    /// Pick the encoding of undecoded region strings
+    fn detect(strings: &[&[u8]]) -> Self {
+        if strings.iter().all(|s| std::str::from_utf8(s).is_ok()) {
+            TextEncoding::Utf8
+        } else if strings.iter().all(|s| TextEncoding::Gb18030.decode(s, true).is_some()) {
+            TextEncoding::Gb18030
+        } else {
+            TextEncoding::Utf8
+        }
+    }
+}
+
+impl DbSearcher {
+    /// Pick the encoding of the geo map and of the region records' trailing
+    /// strings, which hold all the text of databases without a geo map
+    pub(super) fn detect_encoding(&self) -> TextEncoding {
+        let mut strings = geo_map_strings(self.geo_map_data.as_deref().unwrap_or_default());
+        let mut seen = HashSet::new();
+        for bytes in self.index_bytes().unwrap_or_default().chunks_exact(self.record_len) {
+            let record = self.parse_record(bytes);
+            if seen.insert(record.data_ptr) {
+                strings.extend(self.trailing_string(record.data_ptr, record.data_len));
+            }
+        }
+        TextEncoding::detect(&strings)
+    }
+
+    /// Undecoded trailing string of the region record at `ptr`, if well-formed
+    fn trailing_string(&self, ptr: usize, len: usize) -> Option<&[u8]> {
+        let region_bytes = self.data.get(self.start_offset.checked_add(ptr)?..)?.get(..len)?;
+        let mut buf = Cursor::new(region_bytes);
+        let _ = rmp::decode::read_int::<u64, _>(&mut buf);
+        let str_len = rmp::decode::read_str_len(&mut buf).ok()?;
+        region_bytes.get(buf.position() as usize..)?.get(..str_len as usize)
+    }
+}
+
+/// Every string of the geo map, up to the first malformed row
+fn geo_map_strings(geo_map: &[u8]) -> Vec<&[u8]> {
+    let mut strings = Vec::new();
+    let mut buf = Cursor::new(geo_map);
+    while (buf.position() as usize) < geo_map.len() {
+        let Ok(columns) = rmp::decode::read_array_len(&mut buf) else {
+            break;
+        };
+        for _ in 0..columns {
+            let Ok(len) = rmp::decode::read_str_len(&mut buf) else {
+                return strings;
+            };
+            let pos = buf.position() as usize;
+            let Some(bytes) = geo_map.get(pos..).and_then(|rest| rest.get(..len as usize)) else {
+                return strings;
+            };
+            strings.push(bytes);
+            buf.set_position((pos + bytes.len()) as u64);
+        }
+    }
+    strings
+}
@@ -0,0 +1,469 @@
+//! Database integrity verification
+//!
+//! Walks the whole file once and reports every structural problem instead of
+//! stopping at the first one, so a broken database can be rejected in CI
+//! before it reaches production.
+
+use super::direct::{NO_MATCH, SPLIT};
+use super::{
+    slice_at, BTreeHeader, DirectTable, CzdbError, DbSearcher, IndexRecord, IpType, MemoryIndex, Section, HEADER_BLOCK_LENGTH,
+    SUPER_PART_LENGTH,
+};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::net::Ipv4Addr;
+use std::io::Cursor;
+
+/// Category of a problem found by [`DbSearcher::verify`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyCheck {
+    /// Super header pointers are inconsistent
+    Header,
+    /// Index records are unsorted, overlapping or have start > end
+    IndexOrder,
+    /// A record's `data_ptr`/`data_len` points outside the file
+    RecordBounds,
+    /// A region record or the geo row it references fails to decode
+    RegionDecode,
+    /// The geo map is not a sequence of msgpack string arrays
+    GeoMap,
+    /// BTree header pointers are not monotonic or disagree with the index
+    BTreeHeader,
+    /// Memory and BTree mode return different results for a range boundary
+    ModeAgreement,
+    /// A Direct mode /24 entry disagrees with the index
+    DirectTable,
+}
+
+impl VerifyCheck {
+    /// Stable, machine-readable check name
+    pub fn code(&self) -> &'static str {
+        match self {
+            VerifyCheck::Header => "HEADER",
+            VerifyCheck::IndexOrder => "INDEX_ORDER",
+            VerifyCheck::RecordBounds => "RECORD_BOUNDS",
+            VerifyCheck::RegionDecode => "REGION_DECODE",
+            VerifyCheck::GeoMap => "GEO_MAP",
+            VerifyCheck::BTreeHeader => "BTREE_HEADER",
+            VerifyCheck::ModeAgreement => "MODE_AGREEMENT",
+            VerifyCheck::DirectTable => "DIRECT_TABLE",
+        }
+    }
+}
+
+/// A single problem, located by absolute file offset
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerifyIssue {
+    pub check: VerifyCheck,
+    pub offset: usize,
+    pub message: String,
+}
+
+/// Result of [`DbSearcher::verify`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct VerifyReport {
+    pub ip_type: IpType,
+    /// Number of index records
+    pub records: usize,
+    /// Number of distinct region records referenced by the index
+    pub regions: usize,
+    /// Number of rows in the geo map
+    pub geo_rows: usize,
+    /// Number of BTree header entries
+    pub header_entries: usize,
+    /// Number of addresses looked up in both modes
+    pub boundaries_checked: usize,
+    /// First [`VerifyReport::MAX_ISSUES`] problems found
+    pub issues: Vec<VerifyIssue>,
+    /// Total number of problems, including those not kept in `issues`
+    pub issue_count: usize,
+}
+
+impl VerifyReport {
+    /// Maximum number of issues kept in the report
+    pub const MAX_ISSUES: usize = 1000;
+
+    /// True when no problem was found
+    pub fn is_ok(&self) -> bool {
+        self.issue_count == 0
+    }
+
+    fn push(&mut self, check: VerifyCheck, offset: usize, message: String) {
+        self.issue_count += 1;
+        if self.issues.len() < Self::MAX_ISSUES {
+            self.issues.push(VerifyIssue { check, offset, message });
+        }
+    }
+}
+
+impl DbSearcher {
+    /// Check the whole database for structural problems
+    ///
+    /// Verifies that the index is sorted and non-overlapping, every record's region
+    /// pointer lies inside the file, every region record and geo row decodes, the BTree
+    /// header is monotonic and consistent with the index, and that Memory and BTree
+    /// mode agree on every range boundary. Works in any search mode; in Direct mode
+    /// every /24 entry of the table is checked against the index as well.
+    pub fn verify(&self) -> VerifyReport {
+        let mut report = VerifyReport {
+            ip_type: self.ip_type,
+            records: 0,
+            regions: 0,
+            geo_rows: 0,
+            header_entries: 0,
+            boundaries_checked: 0,
+            issues: Vec::new(),
+            issue_count: 0,
+        };
+
+        self.verify_geo_map(&mut report);
+
+        let records = match self.index_bytes() {
+            Ok(bytes) => {
+                if !(self.end_index_ptr - self.start_index_ptr).is_multiple_of(self.record_len) {
+                    report.push(
+                        VerifyCheck::Header,
+                        self.start_offset + 13,
+                        format!(
+                            "index span {}..{} is not a multiple of the {}-byte record length",
+                            self.start_index_ptr, self.end_index_ptr, self.record_len
+                        ),
+                    );
+                }
+                bytes.chunks_exact(self.record_len).map(|r| self.parse_record(r)).collect::<Vec<_>>()
+            }
+            Err(e) => {
+                report.push(VerifyCheck::Header, e.offset().unwrap_or(self.start_offset), e.to_string());
+                return report;
+            }
+        };
+        report.records = records.len();
+
+        self.verify_records(&records, &mut report);
+
+        // Borrowed when the mode already built them, built just for the check otherwise
+        let header = match &self.btree_header {
+            Some(header) => Ok(Cow::Borrowed(header)),
+            None => self.build_btree_index(&self.data).map(Cow::Owned),
+        };
+        let index = match &self.memory_index {
+            Some(index) => Ok(Cow::Borrowed(index)),
+            None => self.build_memory_index(&self.data).map(Cow::Owned),
+        };
+        match (header, index) {
+            (Ok(header), Ok(index)) => {
+                report.header_entries = header.header_ptr.len();
+                self.verify_btree_header(&header, &mut report);
+                self.verify_modes(&records, &index, &header, &mut report);
+                if let Some(table) = &self.direct_table {
+                    self.verify_direct_table(table, &records, &mut report);
+                }
+            }
+            (Err(e), _) => report.push(VerifyCheck::BTreeHeader, e.offset().unwrap_or(self.start_offset), e.to_string()),
+            (_, Err(e)) => report.push(VerifyCheck::Header, e.offset().unwrap_or(self.start_offset), e.to_string()),
+        }
+
+        report
+    }
+
+    /// Absolute file offset of the index record at `idx`
+    fn record_offset(&self, idx: usize) -> usize {
+        self.start_offset + self.start_index_ptr + idx * self.record_len
+    }
+
+    fn verify_geo_map(&self, report: &mut VerifyReport) {
+        let geo_map_data = match &self.geo_map_data {
+            Some(data) => data,
+            None => return,
+        };
+
+        let mut buf = Cursor::new(geo_map_data.as_slice());
+        while (buf.position() as usize) < geo_map_data.len() {
+            let row_offset = self.geo_map_offset + buf.position() as usize;
+            let columns = match rmp::decode::read_array_len(&mut buf) {
+                Ok(columns) => columns,
+                Err(e) => {
+                    report.push(VerifyCheck::GeoMap, row_offset, format!("row {}: {}", report.geo_rows, e));
+                    return;
+                }
+            };
+            for _ in 0..columns {
+                let next = rmp::decode::read_str_len(&mut buf)
+                    .ok()
+                    .and_then(|len| (buf.position() as usize).checked_add(len as usize))
+                    .filter(|&end| end <= geo_map_data.len());
+                match next {
+                    Some(end) => buf.set_position(end as u64),
+                    None => {
+                        report.push(
+                            VerifyCheck::GeoMap,
+                            row_offset,
+                            format!("row {}: column is not a complete msgpack string", report.geo_rows),
+                        );
+                        return;
+                    }
+                }
+            }
+            report.geo_rows += 1;
+        }
+    }
+
+    fn verify_records(&self, records: &[IndexRecord], report: &mut VerifyReport) {
+        let mut checked_regions = HashSet::new();
+
+        for (i, record) in records.iter().enumerate() {
+            let offset = self.record_offset(i);
+
+            if record.start > record.end {
+                report.push(
+                    VerifyCheck::IndexOrder,
+                    offset,
+                    format!("record {} starts at {} after its end {}", i, self.to_ip_addr(record.start), self.to_ip_addr(record.end)),
+                );
+            }
+            if let Some(prev) = i.checked_sub(1).map(|p| &records[p]) {
+                if record.start <= prev.end {
+                    report.push(
+                        VerifyCheck::IndexOrder,
+                        offset,
+                        format!(
+                            "record {} starts at {}, not after the end {} of the previous record",
+                            i, self.to_ip_addr(record.start), self.to_ip_addr(prev.end)
+                        ),
+                    );
+                }
+            }
+
+            if !checked_regions.insert(record.data_ptr) {
+                continue;
+            }
+            let region_end = self.start_offset.checked_add(record.data_ptr).and_then(|o| o.checked_add(record.data_len));
+            if record.data_ptr == 0 || region_end.is_none_or(|end| end > self.data.len()) {
+                report.push(
+                    VerifyCheck::RecordBounds,
+                    offset,
+                    format!(
+                        "record {} points at {} bytes at {}, outside the {}-byte file",
+                        i, record.data_len, record.data_ptr, self.data.len()
+                    ),
+                );
+                continue;
+            }
+            if let Err(e) = self.check_region(record.data_ptr, record.data_len) {
+                report.push(VerifyCheck::RegionDecode, e.offset().unwrap_or(offset), format!("record {}: {}", i, e));
+            }
+        }
+
+        report.regions = checked_regions.len();
+    }
+
+    /// Strict version of `get_region`: every field must be present and decode
+    fn check_region(&self, ptr: usize, len: usize) -> Result<(), CzdbError> {
+        let offset = self.start_offset + ptr;
+        let region_bytes = slice_at(&self.data, Section::Region, offset, len)?;
+        let mut buf = Cursor::new(region_bytes);
+        let msgpack_err = |buf: &Cursor<&[u8]>, source| CzdbError::MsgpackError {
+            section: Section::Region,
+            offset: offset + buf.position() as usize,
+            source,
+        };
+
+        let geo_pos_mix: u64 = rmp::decode::read_int(&mut buf).map_err(|e| CzdbError::InvalidFormat {
+            section: Section::Region,
+            offset,
+            reason: format!("geo pointer is not a msgpack integer: {}", e),
+        })?;
+        if geo_pos_mix != 0 {
+            let geo_map_data = self.geo_map_data.as_deref().ok_or(CzdbError::InvalidFormat {
+                section: Section::Region,
+                offset,
+                reason: "references the geo map, but the database has none".to_string(),
+            })?;
+            let geo_len = ((geo_pos_mix >> 24) & 0xFF) as usize;
+            let geo_ptr = (geo_pos_mix & 0x00FFFFFF) as usize;
+            self.append_geo_string(geo_map_data, geo_ptr, geo_len, true, &mut String::new())?;
+        }
+
+        let str_len = rmp::decode::read_str_len(&mut buf).map_err(|e| msgpack_err(&buf, e))? as usize;
+        let pos = buf.position() as usize;
+        if region_bytes.len() - pos < str_len {
+            return Err(CzdbError::Truncated {
+                section: Section::Region,
+                offset: offset + pos,
+                needed: str_len,
+                available: region_bytes.len() - pos,
+            });
+        }
+        self.decode_text(&region_bytes[pos..pos + str_len], true, Section::Region, offset + pos)?;
+        Ok(())
+    }
+
+    fn verify_btree_header(&self, header: &BTreeHeader, report: &mut VerifyReport) {
+        let header_offset = self.start_offset + SUPER_PART_LENGTH;
+
+        if header.header_ptr.is_empty() {
+            report.push(VerifyCheck::BTreeHeader, header_offset, "header has no entries".to_string());
+            return;
+        }
+
+        for (i, (&ptr, sip)) in header.header_ptr.iter().zip(&header.header_sip).enumerate() {
+            let offset = header_offset + i * HEADER_BLOCK_LENGTH;
+
+            if i > 0 && ptr <= header.header_ptr[i - 1] {
+                report.push(
+                    VerifyCheck::BTreeHeader,
+                    offset,
+                    format!("entry {} pointer {} does not follow {}", i, ptr, header.header_ptr[i - 1]),
+                );
+            }
+            if ptr < self.start_index_ptr
+                || ptr > self.end_index_ptr
+                || !(ptr - self.start_index_ptr).is_multiple_of(self.record_len)
+            {
+                report.push(
+                    VerifyCheck::BTreeHeader,
+                    offset,
+                    format!("entry {} pointer {} is not an index record", i, ptr),
+                );
+                continue;
+            }
+
+            let record_start = self.data.get(self.start_offset + ptr..).and_then(|r| r.get(..self.ip_bytes_len));
+            if record_start != Some(&sip[..self.ip_bytes_len]) {
+                report.push(
+                    VerifyCheck::BTreeHeader,
+                    offset,
+                    format!("entry {} start IP differs from the record it points at", i),
+                );
+            }
+        }
+
+        if header.header_ptr[0] != self.start_index_ptr {
+            report.push(
+                VerifyCheck::BTreeHeader,
+                header_offset,
+                format!("first entry points at {}, index starts at {}", header.header_ptr[0], self.start_index_ptr),
+            );
+        }
+    }
+
+    /// Look up the first and last address of every range, and the first address of
+    /// every gap, in both modes and compare with the record itself
+    fn verify_modes(&self, records: &[IndexRecord], index: &MemoryIndex, header: &BTreeHeader, report: &mut VerifyReport) {
+        let max = match self.ip_type {
+            IpType::Ipv4 => u32::MAX as u128,
+            IpType::Ipv6 => u128::MAX,
+        };
+
+        let check = |ip: u128, expected: Option<&IndexRecord>, idx: usize, report: &mut VerifyReport| {
+            report.boundaries_checked += 1;
+            let (memory, btree) = match self.ip_type {
+                IpType::Ipv4 => (
+                    self.memory_search_ipv4(index, ip as u32),
+                    self.btree_search(header, &(ip as u32).to_be_bytes(), ip),
+                ),
+                IpType::Ipv6 => (
+                    self.memory_search_ipv6(index, ip),
+                    self.btree_search(header, &ip.to_be_bytes(), ip),
+                ),
+            };
+            let describe = |r: Option<&IndexRecord>| match r {
+                Some(r) => format!("{}-{}", self.to_ip_addr(r.start), self.to_ip_addr(r.end)),
+                None => "no match".to_string(),
+            };
+            let btree = match btree {
+                Ok(btree) => btree,
+                Err(e) => {
+                    report.push(VerifyCheck::ModeAgreement, self.record_offset(idx), format!("{}: BTree lookup failed: {}", self.to_ip_addr(ip), e));
+                    return;
+                }
+            };
+            if memory.as_ref() != expected || btree.as_ref() != expected {
+                report.push(
+                    VerifyCheck::ModeAgreement,
+                    self.record_offset(idx),
+                    format!(
+                        "{}: expected {}, Memory found {}, BTree found {}",
+                        self.to_ip_addr(ip),
+                        describe(expected),
+                        describe(memory.as_ref()),
+                        describe(btree.as_ref())
+                    ),
+                );
+            }
+            if let Some(table) = &self.direct_table {
+                let direct = self.direct_search_ipv4(table, index, ip as u32);
+                if direct.as_ref() != expected {
+                    report.push(
+                        VerifyCheck::ModeAgreement,
+                        self.record_offset(idx),
+                        format!("{}: expected {}, Direct found {}", self.to_ip_addr(ip), describe(expected), describe(direct.as_ref())),
+                    );
+                }
+            }
+        };
+
+        let mut next_free = 0u128;
+        for (i, record) in records.iter().enumerate() {
+            if record.start > record.end || (i > 0 && record.start < next_free) {
+                // Already reported as an ordering problem, lookups are undefined
+                continue;
+            }
+            if record.start > next_free {
+                check(next_free, None, i, report);
+            }
+            // BTree mode treats a zero data pointer as a miss
+            let expected = (record.data_ptr != 0).then_some(record);
+            check(record.start, expected, i, report);
+            if record.end != record.start {
+                check(record.end, expected, i, report);
+            }
+            if record.end == max {
+                return;
+            }
+            next_free = record.end + 1;
+        }
+        if !records.is_empty() {
+            check(next_free, None, records.len() - 1, report);
+        }
+    }
+
+    /// Direct mode: every /24 entry must name the record covering it, or be
+    /// "no match" / "split" exactly when no record / only part of a record does
+    fn verify_direct_table(&self, table: &DirectTable, records: &[IndexRecord], report: &mut VerifyReport) {
+        if !records.iter().all(|r| r.start <= r.end) || !records.windows(2).all(|w| w[0].end < w[1].start) {
+            // Already reported as an ordering problem
+            return;
+        }
+        let describe = |entry: u32| match entry {
+            NO_MATCH => "no match".to_string(),
+            SPLIT => "split".to_string(),
+            idx => format!("record {}", idx),
+        };
+
+        let mut next = 0;
+        for (block, &entry) in table.entries.iter().enumerate() {
+            let (first, last) = ((block as u128) << 8, (block as u128) << 8 | 0xFF);
+            while next < records.len() && records[next].end < first {
+                next += 1;
+            }
+            let expected = match records.get(next) {
+                Some(record) if record.start <= first && record.end >= last => next as u32,
+                Some(record) if record.start <= last => SPLIT,
+                _ => NO_MATCH,
+            };
+            if entry != expected {
+                report.push(
+                    VerifyCheck::DirectTable,
+                    self.record_offset(next.min(records.len().saturating_sub(1))),
+                    format!(
+                        "{}/24: table has {}, index has {}",
+                        Ipv4Addr::from((block as u32) << 8),
+                        describe(entry),
+                        describe(expected)
+                    ),
+                );
+            }
+        }
+    }
+}
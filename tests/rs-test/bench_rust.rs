@@ -1,4 +1,6 @@
-use czdb_rs::searcher::{DbSearcher, SearchMode};
+mod common;
+
+use czdb_rs::searcher::{DbSearcher, IndexLayout, SearchMode, SearchOptions};
 use std::fs;
 use std::io::{BufRead, BufReader, BufWriter, Write};
 use std::path::Path;
@@ -171,41 +173,189 @@ fn bench_ipv6_btree() {
 /// Run all benchmarks and print comparison table
 #[test]
 fn bench_all_modes() {
+    let results = vec![
+        // IPv4 benchmarks
+        run_benchmark_mode(
+            "Rust IPv4",
+            SearchMode::Memory,
+            "czdb/cz88_public_v4.czdb",
+            "tests/IPV4.txt",
+            "tests/output/rust_ipv4_memory.txt",
+        ),
+        run_benchmark_mode(
+            "Rust IPv4 BTree",
+            SearchMode::BTree,
+            "czdb/cz88_public_v4.czdb",
+            "tests/IPV4.txt",
+            "tests/output/rust_ipv4_btree.txt",
+        ),
+        // IPv6 benchmarks
+        run_benchmark_mode(
+            "Rust IPv6",
+            SearchMode::Memory,
+            "czdb/cz88_public_v6.czdb",
+            "tests/IPV6.txt",
+            "tests/output/rust_ipv6_memory.txt",
+        ),
+        run_benchmark_mode(
+            "Rust IPv6 BTree",
+            SearchMode::BTree,
+            "czdb/cz88_public_v6.czdb",
+            "tests/IPV6.txt",
+            "tests/output/rust_ipv6_btree.txt",
+        ),
+    ];
+
+    print_table(&results);
+}
+
+/// Time Memory mode lookups of every IP in `input_path`, repeated `rounds` times.
+/// Uses the real database when present, otherwise one synthesized from the IP list.
+fn run_memory_benchmark(name: &str, options: SearchOptions, db_path: &str, input_path: &str, rounds: usize) -> BenchResult {
+    let (data, key) = match fs::read(db_path) {
+        Ok(data) => (data, std::env::var("CZDB_SECRET").unwrap_or_else(|_| "YOUR_SECRET_KEY_HERE".to_string())),
+        Err(_) => (common::build_db_from_list(input_path), common::TEST_KEY.to_string()),
+    };
+    let mode = match (options.mode, options.layout) {
+        (SearchMode::Direct, _) => "Direct".to_string(),
+        (_, IndexLayout::Sorted) => format!("Memory/{}", options.jump_bits),
+        (_, IndexLayout::Eytzinger) => "Eytzinger".to_string(),
+    };
+    let searcher = DbSearcher::with_options(data, &key, options).expect("Failed to init searcher");
+
+    let ips: Vec<String> = fs::read_to_string(input_path)
+        .expect("Failed to open input file")
+        .lines()
+        .filter_map(|l| l.split('/').next())
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect();
+
+    let start = Instant::now();
+    for _ in 0..rounds {
+        for ip in &ips {
+            std::hint::black_box(searcher.search_record(ip).ok());
+        }
+    }
+    let total_time_ms = start.elapsed().as_secs_f64() * 1000.0;
+    let count = ips.len() * rounds;
+
+    BenchResult {
+        name: name.to_string(),
+        mode,
+        total_time_ms,
+        avg_time_ms: total_time_ms / count as f64,
+        count,
+        output_file: "-".to_string(),
+    }
+}
+
+/// Compare Memory mode with and without the prefix jump table
+#[test]
+fn bench_jump_table() {
     let mut results = Vec::new();
+    for (name, db_path, input_path) in [
+        ("Rust IPv4", "czdb/cz88_public_v4.czdb", "tests/IPV4.txt"),
+        ("Rust IPv6", "czdb/cz88_public_v6.czdb", "tests/IPV6.txt"),
+    ] {
+        for jump_bits in [0, SearchOptions::DEFAULT_JUMP_BITS] {
+            let options = SearchOptions { mode: SearchMode::Memory, jump_bits, ..SearchOptions::default() };
+            results.push(run_memory_benchmark(name, options, db_path, input_path, 200));
+        }
+    }
 
-    // IPv4 benchmarks
-    results.push(run_benchmark_mode(
-        "Rust IPv4",
-        SearchMode::Memory,
-        "czdb/cz88_public_v4.czdb",
-        "tests/IPV4.txt",
-        "tests/output/rust_ipv4_memory.txt",
-    ));
+    print_table(&results);
+}
 
-    results.push(run_benchmark_mode(
-        "Rust IPv4 BTree",
-        SearchMode::BTree,
-        "czdb/cz88_public_v4.czdb",
-        "tests/IPV4.txt",
-        "tests/output/rust_ipv4_btree.txt",
-    ));
+/// Compare the sorted and Eytzinger Memory mode index layouts
+#[test]
+fn bench_index_layout() {
+    let mut results = Vec::new();
+    for (name, db_path, input_path) in [
+        ("Rust IPv4", "czdb/cz88_public_v4.czdb", "tests/IPV4.txt"),
+        ("Rust IPv6", "czdb/cz88_public_v6.czdb", "tests/IPV6.txt"),
+    ] {
+        for layout in [IndexLayout::Sorted, IndexLayout::Eytzinger] {
+            let options = SearchOptions { layout, ..SearchOptions::default() };
+            results.push(run_memory_benchmark(name, options, db_path, input_path, 200));
+        }
+    }
 
-    // IPv6 benchmarks
-    results.push(run_benchmark_mode(
-        "Rust IPv6",
-        SearchMode::Memory,
-        "czdb/cz88_public_v6.czdb",
-        "tests/IPV6.txt",
-        "tests/output/rust_ipv6_memory.txt",
-    ));
+    print_table(&results);
+}
 
-    results.push(run_benchmark_mode(
-        "Rust IPv6 BTree",
-        SearchMode::BTree,
-        "czdb/cz88_public_v6.czdb",
-        "tests/IPV6.txt",
-        "tests/output/rust_ipv6_btree.txt",
-    ));
+/// Compare Memory and Direct mode on IPv4
+#[test]
+fn bench_direct_mode() {
+    let results: Vec<BenchResult> = [SearchMode::Memory, SearchMode::Direct]
+        .into_iter()
+        .map(|mode| {
+            let options = SearchOptions { mode, ..SearchOptions::default() };
+            run_memory_benchmark("Rust IPv4", options, "czdb/cz88_public_v4.czdb", "tests/IPV4.txt", 200)
+        })
+        .collect();
+
+    print_table(&results);
+}
+
+/// Compare `search` against `search_into` with interned regions
+#[test]
+fn bench_interned_regions() {
+    let mut results = Vec::new();
+    for (name, db_path, input_path) in [
+        ("Rust IPv4", "czdb/cz88_public_v4.czdb", "tests/IPV4.txt"),
+        ("Rust IPv6", "czdb/cz88_public_v6.czdb", "tests/IPV6.txt"),
+    ] {
+        let (data, key) = match fs::read(db_path) {
+            Ok(data) => (data, std::env::var("CZDB_SECRET").unwrap_or_else(|_| "YOUR_SECRET_KEY_HERE".to_string())),
+            Err(_) => (common::build_db_from_list(input_path), common::TEST_KEY.to_string()),
+        };
+        let ips: Vec<String> = fs::read_to_string(input_path)
+            .expect("Failed to open input file")
+            .lines()
+            .filter_map(|l| l.split('/').next())
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty())
+            .collect();
+        let rounds = 200;
+        let count = ips.len() * rounds;
+
+        let plain = DbSearcher::new(data.clone(), &key).expect("Failed to init searcher");
+        let start = Instant::now();
+        for _ in 0..rounds {
+            for ip in &ips {
+                std::hint::black_box(plain.search(ip).ok());
+            }
+        }
+        let total_time_ms = start.elapsed().as_secs_f64() * 1000.0;
+        results.push(BenchResult {
+            name: name.to_string(),
+            mode: "search".to_string(),
+            total_time_ms,
+            avg_time_ms: total_time_ms / count as f64,
+            count,
+            output_file: "-".to_string(),
+        });
+
+        let options = SearchOptions { intern_regions: true, ..SearchOptions::default() };
+        let interned = DbSearcher::with_options(data, &key, options).expect("Failed to init searcher");
+        let mut out = String::new();
+        let start = Instant::now();
+        for _ in 0..rounds {
+            for ip in &ips {
+                std::hint::black_box(interned.search_into(ip, &mut out).ok());
+            }
+        }
+        let total_time_ms = start.elapsed().as_secs_f64() * 1000.0;
+        results.push(BenchResult {
+            name: name.to_string(),
+            mode: "interned".to_string(),
+            total_time_ms,
+            avg_time_ms: total_time_ms / count as f64,
+            count,
+            output_file: "-".to_string(),
+        });
+    }
 
     print_table(&results);
 }
@@ -0,0 +1,254 @@
+//! Synthetic CZDB database builder for tests
+//!
+//! Produces small files with the same layout as the cz88 public databases
+//! (encrypted hyper header, super header, BTree header, index, geo map,
+//! region data) so tests do not depend on the real `czdb/*.czdb` files.
+
+#![allow(dead_code)]
+
+use aes::cipher::{generic_array::GenericArray, BlockEncrypt, KeyInit};
+use aes::Aes128;
+use base64::{engine::general_purpose, Engine as _};
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+/// base64 of "0123456789abcdef"
+pub const TEST_KEY: &str = "MDEyMzQ1Njc4OWFiY2RlZg==";
+pub const TEST_CLIENT_ID: u32 = 42;
+
+/// Number of index records covered by each BTree header entry
+const RECORDS_PER_HEADER: usize = 4;
+
+/// IPv4 ranges as (start, end, region); region columns are tab-separated
+pub const SAMPLE_V4: &[(&str, &str, &str)] = &[
+    ("1.0.1.0", "1.0.1.255", "中国–福建\t电信"),
+    ("1.0.2.0", "1.0.3.255", "中国–福建\t电信"),
+    ("1.0.8.0", "1.0.15.255", "中国–广东\t电信"),
+    ("1.0.32.0", "1.0.63.255", "中国–广东\t电信"),
+    ("1.1.0.0", "1.1.0.255", "中国–福建\t电信"),
+    ("8.8.8.0", "8.8.8.255", "美国"),
+    ("36.50.226.0", "36.50.226.255", "亚太地区"),
+    ("43.176.0.0", "43.191.255.255", "日本"),
+    ("101.226.0.0", "101.226.255.255", "中国–上海–上海\t电信"),
+    ("114.114.114.0", "114.114.114.255", "中国–江苏–南京\t信风网络"),
+    ("202.96.128.0", "202.96.128.255", "中国–广东–广州\t电信"),
+    ("223.5.5.0", "223.5.5.255", "中国–浙江–杭州\t阿里云"),
+];
+
+/// IPv6 ranges as (start, end, region)
+pub const SAMPLE_V6: &[(&str, &str, &str)] = &[
+    ("2001:250::", "2001:253:ffff:ffff:ffff:ffff:ffff:ffff", "中国\t教育网"),
+    ("2001:254::", "2001:254:ffff:ffff:ffff:ffff:ffff:ffff", "中国"),
+    ("2001:c68::", "2001:c68:ffff:ffff:ffff:ffff:ffff:ffff", "中国\t中国电信"),
+    ("2001:da8::", "2001:da8:ffff:ffff:ffff:ffff:ffff:ffff", "中国\t教育网"),
+    ("2001:4860::", "2001:4860:ffff:ffff:ffff:ffff:ffff:ffff", "美国\tGoogle"),
+    ("2408:8000::", "2408:8fff:ffff:ffff:ffff:ffff:ffff:ffff", "中国\t中国联通"),
+    ("240e::", "240e:ff:ffff:ffff:ffff:ffff:ffff:ffff", "中国\t中国电信"),
+];
+
+pub fn sample_v4() -> Vec<u8> {
+    build_db(SAMPLE_V4)
+}
+
+pub fn sample_v6() -> Vec<u8> {
+    build_db(SAMPLE_V6)
+}
+
+fn ip_bytes(ip: &str) -> Vec<u8> {
+    match ip.parse::<IpAddr>().expect("invalid fixture IP") {
+        IpAddr::V4(addr) => addr.octets().to_vec(),
+        IpAddr::V6(addr) => addr.octets().to_vec(),
+    }
+}
+
+fn key_bytes() -> Vec<u8> {
+    general_purpose::STANDARD.decode(TEST_KEY).unwrap()
+}
+
+fn encrypt_aes_ecb(plain: &[u8]) -> Vec<u8> {
+    let key = key_bytes();
+    let cipher = Aes128::new(GenericArray::from_slice(&key));
+
+    // PKCS#7
+    let pad = 16 - plain.len() % 16;
+    let mut out = plain.to_vec();
+    out.resize(out.len() + pad, pad as u8);
+
+    for chunk in out.chunks_mut(16) {
+        cipher.encrypt_block(GenericArray::from_mut_slice(chunk));
+    }
+    out
+}
+
+/// Build a database from sorted, non-overlapping ranges of one IP family
+pub fn build_db(ranges: &[(&str, &str, &str)]) -> Vec<u8> {
+    build_db_encoded(ranges, |text| text.as_bytes().to_vec())
+}
+
+/// Build a database whose region strings are stored as `encode` returns them,
+/// e.g. GBK for legacy files
+pub fn build_db_encoded(ranges: &[(&str, &str, &str)], encode: impl Fn(&str) -> Vec<u8>) -> Vec<u8> {
+    build_db_with(ranges, encode, true)
+}
+
+/// Build a database with no geo map, each region stored whole as the
+/// trailing string of its region record, encoded by `encode`
+pub fn build_db_trailing(ranges: &[(&str, &str, &str)], encode: impl Fn(&str) -> Vec<u8>) -> Vec<u8> {
+    build_db_with(ranges, encode, false)
+}
+
+fn build_db_with(ranges: &[(&str, &str, &str)], encode: impl Fn(&str) -> Vec<u8>, geo_map_regions: bool) -> Vec<u8> {
+    let is_v6 = ip_bytes(ranges[0].0).len() == 16;
+    let ip_len = if is_v6 { 16 } else { 4 };
+    let record_len = ip_len * 2 + 5;
+    let count = ranges.len();
+
+    // Hyper header: version, client id, AES block with (client id, expiry) and random size
+    let random_size = 4usize;
+    let mut plain = Vec::new();
+    plain.extend_from_slice(&((TEST_CLIENT_ID << 20) | 0x3_2101).to_le_bytes());
+    plain.extend_from_slice(&(random_size as u32).to_le_bytes());
+    let encrypted = encrypt_aes_ecb(&plain);
+
+    let mut out = Vec::new();
+    out.extend_from_slice(&1u32.to_le_bytes());
+    out.extend_from_slice(&TEST_CLIENT_ID.to_le_bytes());
+    out.extend_from_slice(&(encrypted.len() as u32).to_le_bytes());
+    out.extend_from_slice(&encrypted);
+    out.extend_from_slice(&[0xAB; 4][..random_size]);
+
+    // Everything below is addressed relative to this offset
+    let start_offset = out.len();
+
+    let mut header_rows: Vec<usize> = (0..count).step_by(RECORDS_PER_HEADER).collect();
+    if *header_rows.last().unwrap() != count - 1 {
+        header_rows.push(count - 1);
+    }
+    let total_header_block_size = header_rows.len() * 20;
+    let start_index_ptr = 17 + total_header_block_size;
+    let end_index_ptr = start_index_ptr + (count - 1) * record_len;
+
+    // Geo map: one msgpack array per distinct region, XOR-ed with the key
+    let mut geo_map = Vec::new();
+    let mut geo_rows: HashMap<&str, (usize, usize)> = HashMap::new();
+    for (_, _, region) in ranges {
+        if !geo_map_regions || geo_rows.contains_key(region) {
+            continue;
+        }
+        let columns: Vec<&str> = region.split('\t').collect();
+        let ptr = geo_map.len();
+        rmp::encode::write_array_len(&mut geo_map, columns.len() as u32).unwrap();
+        for column in columns {
+            let bytes = encode(column);
+            rmp::encode::write_str_len(&mut geo_map, bytes.len() as u32).unwrap();
+            geo_map.extend_from_slice(&bytes);
+        }
+        geo_rows.insert(region, (ptr, geo_map.len() - ptr));
+    }
+    let key = key_bytes();
+    let encrypted_geo_map: Vec<u8> = geo_map
+        .iter()
+        .enumerate()
+        .map(|(i, b)| b ^ key[i % 16])
+        .collect();
+
+    // Region data follows the geo map: msgpack geo pointer + empty trailing
+    // string, or no geo pointer and the whole region as trailing string
+    let region_data_ptr = end_index_ptr + record_len + 4 + 4 + encrypted_geo_map.len();
+    let mut region_data = Vec::new();
+    let mut region_ptrs: HashMap<&str, (usize, usize)> = HashMap::new();
+    for (_, _, region) in ranges {
+        if region_ptrs.contains_key(region) {
+            continue;
+        }
+        let ptr = region_data.len();
+        if geo_map_regions {
+            let (geo_ptr, geo_len) = geo_rows[region];
+            rmp::encode::write_uint(&mut region_data, ((geo_len << 24) | geo_ptr) as u64).unwrap();
+            rmp::encode::write_str(&mut region_data, "").unwrap();
+        } else {
+            let bytes = encode(region);
+            rmp::encode::write_uint(&mut region_data, 0).unwrap();
+            rmp::encode::write_str_len(&mut region_data, bytes.len() as u32).unwrap();
+            region_data.extend_from_slice(&bytes);
+        }
+        region_ptrs.insert(region, (region_data_ptr + ptr, region_data.len() - ptr));
+    }
+
+    // Super header
+    out.push(if is_v6 { 1 } else { 0 });
+    out.extend_from_slice(&0u32.to_le_bytes());
+    out.extend_from_slice(&(start_index_ptr as u32).to_le_bytes());
+    out.extend_from_slice(&(total_header_block_size as u32).to_le_bytes());
+    out.extend_from_slice(&(end_index_ptr as u32).to_le_bytes());
+
+    // BTree header: start IP (padded to 16 bytes) and pointer of every n-th record
+    for row in &header_rows {
+        let mut sip = ip_bytes(ranges[*row].0);
+        sip.resize(16, 0);
+        out.extend_from_slice(&sip);
+        out.extend_from_slice(&((start_index_ptr + row * record_len) as u32).to_le_bytes());
+    }
+
+    // Index records
+    for (start, end, region) in ranges {
+        let (data_ptr, data_len) = region_ptrs[region];
+        out.extend_from_slice(&ip_bytes(start));
+        out.extend_from_slice(&ip_bytes(end));
+        out.extend_from_slice(&(data_ptr as u32).to_le_bytes());
+        out.push(data_len as u8);
+    }
+
+    // Column selection (first two geo columns, none without a geo map) and geo map
+    out.extend_from_slice(&(if geo_map_regions { 0b110u32 } else { 0 }).to_le_bytes());
+    out.extend_from_slice(&(encrypted_geo_map.len() as u32).to_le_bytes());
+    out.extend_from_slice(&encrypted_geo_map);
+
+    out.extend_from_slice(&region_data);
+    assert_eq!(out.len(), start_offset + region_data_ptr + region_data.len());
+
+    out
+}
+
+/// Ranges for every start IP listed in `path` (one IP or CIDR per line, as in
+/// `tests/IPV4.txt`). Each range covers the first half of the space up to the
+/// next start so lookups also hit gaps.
+pub fn ranges_from_list(path: &str) -> Vec<(String, String, String)> {
+    const REGIONS: &[&str] = &["中国–福建\t电信", "中国–广东\t电信", "美国", "日本", "中国\t教育网"];
+
+    let text = std::fs::read_to_string(path).expect("Failed to read IP list");
+    let mut starts: Vec<IpAddr> = text
+        .lines()
+        .filter_map(|l| l.split('/').next()?.trim().parse().ok())
+        .collect();
+    starts.sort();
+    starts.dedup();
+
+    let as_u128 = |ip: &IpAddr| match ip {
+        IpAddr::V4(addr) => u32::from(*addr) as u128,
+        IpAddr::V6(addr) => u128::from(*addr),
+    };
+    let from_u128 = |value: u128| match starts[0] {
+        IpAddr::V4(_) => IpAddr::V4((value as u32).into()),
+        IpAddr::V6(_) => IpAddr::V6(value.into()),
+    };
+    let max = if starts[0].is_ipv4() { u32::MAX as u128 } else { u128::MAX };
+
+    starts
+        .iter()
+        .enumerate()
+        .map(|(i, start)| {
+            let next = starts.get(i + 1).map_or(max, |n| as_u128(n) - 1);
+            let end = as_u128(start) + (next - as_u128(start)) / 2;
+            (start.to_string(), from_u128(end).to_string(), REGIONS[i % REGIONS.len()].to_string())
+        })
+        .collect()
+}
+
+/// Build a database from [`ranges_from_list`]
+pub fn build_db_from_list(path: &str) -> Vec<u8> {
+    let ranges = ranges_from_list(path);
+    let borrowed: Vec<(&str, &str, &str)> =
+        ranges.iter().map(|(s, e, r)| (s.as_str(), e.as_str(), r.as_str())).collect();
+    build_db(&borrowed)
+}
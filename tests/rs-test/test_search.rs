@@ -1,5 +1,10 @@
-use czdb_rs::searcher::{DbSearcher, SearchMode};
+mod common;
+
+use czdb_rs::searcher::{
+    BatchRegions, Carrier, CzdbError, CzdbKey, AdminDivision, Country, DbSearcher, DirectTable, DivisionKind, IndexLayout, IspCategory, Language, ListFormat, ListOptions, Location, RangeOverlap, RegionFilter, RegionMatch, SearchMode, SearchOptions, Section, TextEncoding, VerifyCheck,
+};
 use std::fs;
+use std::net::IpAddr;
 use std::path::Path;
 
 /// Test IPv4 search with Memory mode (default)
@@ -99,3 +104,1099 @@ fn test_modes_consistent() {
         assert_eq!(memory_result, btree_result, "Memory and BTree results differ for {}", ip);
     }
 }
+
+/// Test that search_record reports the matched range in both modes
+#[test]
+fn test_search_record() {
+    for mode in [SearchMode::Memory, SearchMode::BTree] {
+        let searcher = DbSearcher::with_mode(common::sample_v4(), common::TEST_KEY, mode)
+            .expect("Failed to init searcher");
+
+        let record = searcher.search_record("1.0.9.1").expect("Search failed").expect("No match");
+        assert_eq!(record.start, "1.0.8.0".parse::<IpAddr>().unwrap());
+        assert_eq!(record.end, "1.0.15.255".parse::<IpAddr>().unwrap());
+        assert_eq!(record.region, "中国–广东\t电信");
+
+        // Gap between ranges
+        assert_eq!(searcher.search_record("1.0.4.1").expect("Search failed"), None);
+        assert_eq!(searcher.search("1.0.4.1").expect("Search failed"), "Unknown");
+    }
+
+    let searcher = DbSearcher::new(common::sample_v6(), common::TEST_KEY).expect("Failed to init searcher");
+    let record = searcher.search_record("2001:da8::1").expect("Search failed").expect("No match");
+    assert_eq!(record.start, "2001:da8::".parse::<IpAddr>().unwrap());
+    assert_eq!(record.region, "中国\t教育网");
+    assert!(searcher.search_record("8.8.8.8").is_err());
+}
+
+/// Test search mode conversion from the numeric binding values
+#[test]
+fn test_search_mode_from_u8() {
+    assert_eq!(SearchMode::try_from(0).unwrap(), SearchMode::Memory);
+    assert_eq!(SearchMode::try_from(1).unwrap(), SearchMode::BTree);
+    assert_eq!(SearchMode::try_from(2).unwrap(), SearchMode::Direct);
+
+    let err = SearchMode::try_from(3).unwrap_err();
+    assert_eq!(err.code(), "INVALID_SEARCH_MODE");
+}
+
+/// Test integer batch search returns deduplicated region ids
+#[test]
+fn test_search_batch_ids() {
+    for mode in [SearchMode::Memory, SearchMode::BTree] {
+        let searcher = DbSearcher::with_mode(common::sample_v4(), common::TEST_KEY, mode)
+            .expect("Failed to init searcher");
+
+        let ips = [0x0100_0101, 0x0808_0808, 0x0100_0401, 0x0100_0201, 0x0101_0001];
+        let batch = searcher.search_batch_v4(&ips).expect("Batch search failed");
+
+        assert_eq!(batch.ids, vec![0, 1, BatchRegions::NO_MATCH, 0, 0]);
+        assert_eq!(batch.regions, vec!["中国–福建\t电信".to_string(), "美国".to_string()]);
+        assert!(searcher.search_batch_v6(&[1]).is_err());
+    }
+
+    let searcher = DbSearcher::new(common::sample_v6(), common::TEST_KEY).expect("Failed to init searcher");
+    let ip: u128 = "2001:da8::1".parse::<std::net::Ipv6Addr>().unwrap().into();
+    let batch = searcher.search_batch_v6(&[ip, 0]).expect("Batch search failed");
+    assert_eq!(batch.ids, vec![0, BatchRegions::NO_MATCH]);
+    assert_eq!(batch.regions, vec!["中国\t教育网".to_string()]);
+}
+
+/// Test errors report the failing section, offset and expected/actual values
+#[test]
+fn test_error_context() {
+    let data = common::sample_v4();
+
+    let err = DbSearcher::new(data[..20].to_vec(), common::TEST_KEY).err().unwrap();
+    assert!(matches!(
+        err,
+        CzdbError::Truncated { section: Section::HyperHeader, offset: 12, needed: 16, available: 8 }
+    ));
+    assert_eq!(err.code(), "TRUNCATED");
+
+    let mut wrong_id = data.clone();
+    wrong_id[4..8].copy_from_slice(&7u32.to_le_bytes());
+    let err = DbSearcher::new(wrong_id, common::TEST_KEY).err().unwrap();
+    assert!(matches!(err, CzdbError::ClientIdMismatch { expected: 7, actual: 42 }));
+    assert_eq!(err.code(), "CLIENT_ID_MISMATCH");
+
+    // Dropping the tail cuts into the region records referenced by the index
+    let searcher = DbSearcher::with_mode(data[..data.len() - 4].to_vec(), common::TEST_KEY, SearchMode::BTree)
+        .expect("Failed to init searcher");
+    let err = searcher.search("223.5.5.5").unwrap_err();
+    assert_eq!(err.section(), Some(Section::Region));
+    assert_eq!(err.code(), "TRUNCATED");
+    assert!(err.offset().unwrap() < data.len());
+}
+
+/// Test truncated and corrupted databases yield errors instead of panics
+#[test]
+fn test_malformed_databases_do_not_panic() {
+    fn exercise(data: Vec<u8>) {
+        for mode in [SearchMode::Memory, SearchMode::BTree] {
+            if let Ok(searcher) = DbSearcher::with_mode(data.clone(), common::TEST_KEY, mode) {
+                for ip in ["1.0.1.1", "8.8.8.8", "223.5.5.5", "255.255.255.255", "2001:da8::1", "240e::1"] {
+                    let _ = searcher.search(ip);
+                }
+                let _ = searcher.search_batch_v4(&[0, 0x0808_0808, u32::MAX]);
+                let _ = searcher.search_batch_v6(&[0, u128::MAX]);
+                let _ = searcher.region_dictionary();
+                let _ = searcher.search_region(&RegionFilter::default());
+                let _ = searcher.region_cidrs(&RegionFilter::default());
+                let _ = searcher.search_prefix("0.0.0.0/0");
+                let _ = searcher.search_prefix("::/0");
+            }
+        }
+    }
+
+    for sample in [common::sample_v4(), common::sample_v6()] {
+        for len in 0..sample.len() {
+            exercise(sample[..len].to_vec());
+        }
+        for i in 0..sample.len() {
+            for value in [0x00, 0xFF, sample[i] ^ 0x80] {
+                let mut corrupt = sample.clone();
+                corrupt[i] = value;
+                exercise(corrupt);
+            }
+        }
+    }
+}
+
+/// Test verification passes on intact databases and both modes agree on every boundary
+#[test]
+fn test_verify_sample_databases() {
+    for (data, records) in [(common::sample_v4(), common::SAMPLE_V4.len()), (common::sample_v6(), common::SAMPLE_V6.len())] {
+        for mode in [SearchMode::Memory, SearchMode::BTree] {
+            let searcher = DbSearcher::with_mode(data.clone(), common::TEST_KEY, mode).expect("Failed to init searcher");
+            let report = searcher.verify();
+            assert!(report.is_ok(), "{:?}", report.issues);
+            assert_eq!(report.records, records);
+            assert!(report.geo_rows > 0 && report.regions > 0 && report.header_entries > 0);
+            assert!(report.boundaries_checked >= records * 2);
+        }
+    }
+
+    // Exact range starts and ends, including header block boundaries and the last record
+    for mode in [SearchMode::Memory, SearchMode::BTree] {
+        let searcher = DbSearcher::with_mode(common::sample_v4(), common::TEST_KEY, mode).expect("Failed to init searcher");
+        for (start, end, region) in common::SAMPLE_V4 {
+            assert_eq!(searcher.search(start).unwrap(), *region, "{} in {:?}", start, mode);
+            assert_eq!(searcher.search(end).unwrap(), *region, "{} in {:?}", end, mode);
+        }
+    }
+}
+
+/// Test verification reports corrupted index records and BTree headers
+#[test]
+fn test_verify_reports_corruption() {
+    let data = common::sample_v4();
+    // Fixture layout: 12-byte plain header, 16-byte AES block, 4 random bytes, then the super header
+    let start_offset = 32;
+    let start_index_ptr = u32::from_le_bytes(data[start_offset + 5..start_offset + 9].try_into().unwrap()) as usize;
+    let record = |i: usize| start_offset + start_index_ptr + i * 13;
+
+    let checks = |data: Vec<u8>| -> Vec<VerifyCheck> {
+        let searcher = DbSearcher::with_mode(data, common::TEST_KEY, SearchMode::BTree).expect("Failed to init searcher");
+        let report = searcher.verify();
+        assert_eq!(report.issue_count, report.issues.len());
+        report.issues.iter().map(|issue| issue.check).collect()
+    };
+
+    // Record 2 starts before record 1 ends
+    let mut overlapping = data.clone();
+    overlapping[record(2)..record(2) + 4].copy_from_slice(&[1, 0, 3, 0]);
+    let found = checks(overlapping);
+    assert!(found.contains(&VerifyCheck::IndexOrder));
+
+    // Record 5 points past the end of the file
+    let mut out_of_bounds = data.clone();
+    out_of_bounds[record(5) + 8..record(5) + 12].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert_eq!(checks(out_of_bounds), vec![VerifyCheck::RecordBounds]);
+
+    // Second BTree header entry points at the wrong record
+    let mut bad_header = data.clone();
+    let entry = start_offset + 17 + 20;
+    bad_header[entry + 16..entry + 20].copy_from_slice(&((start_index_ptr + 13) as u32).to_le_bytes());
+    let found = checks(bad_header);
+    assert!(found.contains(&VerifyCheck::BTreeHeader));
+
+    let report = DbSearcher::with_mode(data, common::TEST_KEY, SearchMode::Memory).unwrap().verify();
+    assert!(report.is_ok());
+}
+
+/// Test a wrong key is reported as such and candidate keys are tried in order
+#[test]
+fn test_wrong_key_detection() {
+    // base64 of "fedcba9876543210"
+    const OTHER_KEY: &str = "ZmVkY2JhOTg3NjU0MzIxMA==";
+    let data = common::sample_v4();
+
+    let err = DbSearcher::new(data.clone(), OTHER_KEY).err().unwrap();
+    assert!(matches!(err, CzdbError::WrongKey));
+    assert_eq!(err.code(), "WRONG_KEY");
+
+    // A malformed key is not a wrong key
+    let err = DbSearcher::new(data.clone(), "not base64!").err().unwrap();
+    assert_eq!(err.code(), "DECRYPT_ERROR");
+
+    // A damaged encrypted block is corruption, not a wrong key
+    let mut corrupt = data.clone();
+    corrupt[8..12].copy_from_slice(&15u32.to_le_bytes());
+    let err = DbSearcher::new(corrupt, common::TEST_KEY).err().unwrap();
+    assert_eq!(err.code(), "INVALID_FORMAT");
+
+    assert_eq!(DbSearcher::matching_key(&data, &[OTHER_KEY, "not base64!", common::TEST_KEY]).unwrap(), 2);
+    assert!(matches!(
+        DbSearcher::matching_key(&data, &["not base64!", OTHER_KEY]),
+        Err(CzdbError::WrongKey)
+    ));
+    assert!(matches!(DbSearcher::matching_key::<&str>(&data, &[]), Err(CzdbError::WrongKey)));
+    assert!(matches!(
+        DbSearcher::matching_key(&data[..20], &[common::TEST_KEY]),
+        Err(CzdbError::Truncated { .. })
+    ));
+
+    // Wrong keys that happen to leave valid padding are still wrong keys
+    let keys: Vec<CzdbKey> = (0..2048u32)
+        .map(|i| {
+            let mut key = [0x5A; 16];
+            key[..4].copy_from_slice(&i.to_le_bytes());
+            CzdbKey::from_bytes(key)
+        })
+        .collect();
+    for key in &keys {
+        assert!(matches!(DbSearcher::new(data.clone(), key).err(), Some(CzdbError::WrongKey)));
+    }
+    assert!(matches!(DbSearcher::matching_key(&data, &keys), Err(CzdbError::WrongKey)));
+
+    let searcher = DbSearcher::with_candidate_keys(data, &[OTHER_KEY, common::TEST_KEY], SearchMode::BTree)
+        .expect("Failed to init searcher");
+    assert_eq!(searcher.search("8.8.8.8").unwrap(), "美国");
+}
+
+/// Test key validation and the different ways of loading a key
+#[test]
+fn test_czdb_key() {
+    // base64 of "12345678", too short for AES-128
+    let err = CzdbKey::from_base64("MTIzNDU2Nzg=").unwrap_err();
+    assert_eq!(err.code(), "DECRYPT_ERROR");
+    assert!(DbSearcher::new(common::sample_v4(), "MTIzNDU2Nzg=").is_err());
+    assert!(CzdbKey::from_base64("not base64!").is_err());
+    assert!(CzdbKey::from_slice(&[0; 15]).is_err());
+
+    let key = CzdbKey::from_bytes(*b"0123456789abcdef");
+    assert_eq!(format!("{:?}", key), "CzdbKey(..)");
+    let searcher = DbSearcher::new(common::sample_v4(), &key).expect("Failed to init searcher");
+    assert_eq!(searcher.search("8.8.8.8").unwrap(), "美国");
+
+    let parsed: CzdbKey = format!(" {}\n", common::TEST_KEY).parse().expect("Failed to parse key");
+    assert!(DbSearcher::new(common::sample_v6(), parsed).is_ok());
+
+    let path = std::env::temp_dir().join(format!("czdb-key-{}", std::process::id()));
+    fs::write(&path, format!("{}\n", common::TEST_KEY)).unwrap();
+    let from_file = CzdbKey::from_file(&path);
+    fs::remove_file(&path).unwrap();
+    assert!(DbSearcher::new(common::sample_v4(), from_file.expect("Failed to read key file")).is_ok());
+
+    std::env::set_var("CZDB_TEST_KEY_FROM_ENV", common::TEST_KEY);
+    assert!(DbSearcher::new(common::sample_v4(), CzdbKey::from_env("CZDB_TEST_KEY_FROM_ENV").unwrap()).is_ok());
+    assert_eq!(CzdbKey::from_env("CZDB_TEST_KEY_UNSET").unwrap_err().code(), "IO_ERROR");
+
+    let keys = [CzdbKey::from_bytes([7; 16]), key];
+    assert_eq!(DbSearcher::matching_key(&common::sample_v4(), &keys).unwrap(), 1);
+}
+
+/// Test every Memory mode jump table size and index layout returns the same results as BTree mode
+#[test]
+fn test_memory_index_options_consistent() {
+    for path in ["tests/IPV4.txt", "tests/IPV6.txt"] {
+        let ranges = common::ranges_from_list(path);
+        let data = common::build_db_from_list(path);
+        let btree = DbSearcher::with_mode(data.clone(), common::TEST_KEY, SearchMode::BTree)
+            .expect("Failed to init BTree searcher");
+
+        // Range starts and ends, the addresses around them and the ends of the address space
+        let to_u128 = |ip: &str| match ip.parse::<IpAddr>().unwrap() {
+            IpAddr::V4(addr) => u32::from(addr) as u128,
+            IpAddr::V6(addr) => u128::from(addr),
+        };
+        let max = if path.contains("IPV4") { u32::MAX as u128 } else { u128::MAX };
+        let mut probes = vec![0, max];
+        for (start, end, _) in &ranges {
+            let (start, end) = (to_u128(start), to_u128(end));
+            probes.extend([start, start.saturating_sub(1), end, end.saturating_add(1).min(max)]);
+        }
+        let probes: Vec<String> = probes
+            .into_iter()
+            .map(|ip| if max == u32::MAX as u128 {
+                std::net::Ipv4Addr::from(ip as u32).to_string()
+            } else {
+                std::net::Ipv6Addr::from(ip).to_string()
+            })
+            .collect();
+        let expected: Vec<_> = probes.iter().map(|ip| btree.search_record(ip).unwrap()).collect();
+        assert!(expected.iter().any(Option::is_none) && expected.iter().any(Option::is_some));
+
+        let mut all_options: Vec<SearchOptions> = [0, 8, 16, SearchOptions::MAX_JUMP_BITS]
+            .into_iter()
+            .map(|jump_bits| SearchOptions { jump_bits, ..SearchOptions::default() })
+            .collect();
+        all_options.push(SearchOptions { layout: IndexLayout::Eytzinger, ..SearchOptions::default() });
+
+        for options in all_options {
+            let searcher = DbSearcher::with_options(data.clone(), common::TEST_KEY, options.clone())
+                .expect("Failed to init searcher");
+            for (ip, expected) in probes.iter().zip(&expected) {
+                assert_eq!(&searcher.search_record(ip).unwrap(), expected, "{} with {:?}", ip, options);
+            }
+            assert!(searcher.verify().is_ok());
+        }
+    }
+
+    let options = SearchOptions { jump_bits: SearchOptions::MAX_JUMP_BITS + 1, ..SearchOptions::default() };
+    let err = DbSearcher::with_options(common::sample_v4(), common::TEST_KEY, options).err().unwrap();
+    assert_eq!(err.code(), "INVALID_OPTION");
+}
+
+/// Test the Eytzinger layout on the small samples, whose trees are not complete
+#[test]
+fn test_eytzinger_layout() {
+    let options = SearchOptions { layout: IndexLayout::Eytzinger, ..SearchOptions::default() };
+    for (data, ranges) in [(common::sample_v4(), common::SAMPLE_V4), (common::sample_v6(), common::SAMPLE_V6)] {
+        for len in 1..=ranges.len() {
+            let data = if len == ranges.len() { data.clone() } else { common::build_db(&ranges[..len]) };
+            let searcher = DbSearcher::with_options(data, common::TEST_KEY, options.clone())
+                .expect("Failed to init searcher");
+            for (start, end, region) in &ranges[..len] {
+                assert_eq!(searcher.search(start).unwrap(), *region);
+                assert_eq!(searcher.search(end).unwrap(), *region);
+            }
+            assert!(searcher.verify().is_ok());
+        }
+    }
+
+    let searcher = DbSearcher::with_options(common::sample_v4(), common::TEST_KEY, options).unwrap();
+    assert_eq!(searcher.search("0.0.0.0").unwrap(), "Unknown");
+    assert_eq!(searcher.search("1.0.4.1").unwrap(), "Unknown");
+    assert_eq!(searcher.search("255.255.255.255").unwrap(), "Unknown");
+}
+
+/// Test Direct mode agrees with BTree mode, including /24s split across ranges,
+/// and that its table can be saved and reused
+#[test]
+fn test_direct_mode() {
+    let ranges = common::ranges_from_list("tests/IPV4.txt");
+    let data = common::build_db_from_list("tests/IPV4.txt");
+    let btree = DbSearcher::with_mode(data.clone(), common::TEST_KEY, SearchMode::BTree).unwrap();
+    let direct = DbSearcher::with_mode(data.clone(), common::TEST_KEY, SearchMode::Direct).unwrap();
+    assert_eq!(direct.search_mode(), SearchMode::Direct);
+
+    let mut probes = vec![0, u32::MAX];
+    for (start, end, _) in &ranges {
+        let (start, end) = (u32::from(start.parse::<std::net::Ipv4Addr>().unwrap()), u32::from(end.parse::<std::net::Ipv4Addr>().unwrap()));
+        probes.extend([start, start.saturating_sub(1), start + (end - start) / 2, end, end.saturating_add(1)]);
+    }
+    for ip in probes {
+        let ip = std::net::Ipv4Addr::from(ip).to_string();
+        assert_eq!(direct.search_record(&ip).unwrap(), btree.search_record(&ip).unwrap(), "{}", ip);
+    }
+    let batch = [0x0100_0101, 0x0100_0181, 0x0808_0808];
+    assert_eq!(direct.search_batch_v4(&batch).unwrap(), btree.search_batch_v4(&batch).unwrap());
+
+    let table = direct.direct_table().expect("No direct table");
+    assert!(table.split_blocks() > 0);
+    assert!(direct.verify().is_ok());
+
+    // Save and reload
+    let bytes = table.to_bytes();
+    let loaded = DirectTable::from_bytes(&bytes).expect("Failed to parse table");
+    assert_eq!(&loaded, table);
+    let reused = DbSearcher::with_direct_table(data.clone(), common::TEST_KEY, loaded.clone()).unwrap();
+    assert_eq!(reused.search("1.0.1.1").unwrap(), direct.search("1.0.1.1").unwrap());
+
+    // A table belongs to one database
+    let err = DbSearcher::with_direct_table(common::sample_v4(), common::TEST_KEY, loaded).err().unwrap();
+    assert_eq!(err.code(), "INVALID_OPTION");
+    let err = DirectTable::from_bytes(&bytes[..bytes.len() - 1]).unwrap_err();
+    assert_eq!(err.code(), "TRUNCATED");
+    assert_eq!(err.section(), Some(Section::DirectTable));
+    assert_eq!(DirectTable::from_bytes(b"not a table, just text").unwrap_err().code(), "INVALID_FORMAT");
+
+    // A damaged entry is caught by verify: 1.0.1.0/24 now claims no match
+    let mut damaged = bytes.clone();
+    let entry = 20 + 0x01_00_01 * 4;
+    damaged[entry..entry + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+    let searcher = DbSearcher::with_direct_table(data, common::TEST_KEY, DirectTable::from_bytes(&damaged).unwrap()).unwrap();
+    let checks: Vec<VerifyCheck> = searcher.verify().issues.iter().map(|issue| issue.check).collect();
+    assert!(checks.contains(&VerifyCheck::DirectTable));
+
+    // IPv4 only
+    let err = DbSearcher::with_mode(common::sample_v6(), common::TEST_KEY, SearchMode::Direct).err().unwrap();
+    assert_eq!(err.code(), "INVALID_OPTION");
+}
+
+/// Test interned regions return the same strings as decoding on every lookup
+#[test]
+fn test_interned_regions() {
+    let interned = SearchOptions { intern_regions: true, ..SearchOptions::default() };
+    for mode in [SearchMode::Memory, SearchMode::BTree] {
+        let plain = DbSearcher::with_mode(common::sample_v4(), common::TEST_KEY, mode).unwrap();
+        let searcher = DbSearcher::with_options(common::sample_v4(), common::TEST_KEY, SearchOptions { mode, ..interned.clone() })
+            .expect("Failed to init searcher");
+
+        let mut out = String::new();
+        for (start, end, region) in common::SAMPLE_V4 {
+            for ip in [start, end] {
+                assert_eq!(searcher.search(ip).unwrap(), *region);
+                assert_eq!(searcher.search_record(ip).unwrap(), plain.search_record(ip).unwrap());
+                assert!(searcher.search_into(ip, &mut out).unwrap());
+                assert_eq!(out, *region);
+                assert!(plain.search_into(ip, &mut out).unwrap());
+                assert_eq!(out, *region);
+                assert_eq!(searcher.search_shared(ip).unwrap().as_deref(), Some(*region));
+                assert_eq!(plain.search_shared(ip).unwrap().as_deref(), Some(*region));
+            }
+        }
+
+        // Ranges with the same region share one allocation
+        let a = searcher.search_shared("1.0.1.1").unwrap().unwrap();
+        let b = searcher.search_shared("1.1.0.1").unwrap().unwrap();
+        assert!(std::sync::Arc::ptr_eq(&a, &b));
+
+        assert!(!searcher.search_into("1.0.4.1", &mut out).unwrap());
+        assert!(out.is_empty());
+        assert_eq!(searcher.search_shared("1.0.4.1").unwrap(), None);
+        assert!(searcher.search_into("not an ip", &mut out).is_err());
+
+        let ips = [0x0100_0101, 0x0808_0808, 0x0100_0401];
+        assert_eq!(searcher.search_batch_v4(&ips).unwrap(), plain.search_batch_v4(&ips).unwrap());
+    }
+
+    // Regions that fail to decode are not interned and still report the error
+    let data = common::sample_v4();
+    let options = SearchOptions { mode: SearchMode::BTree, ..interned };
+    let searcher = DbSearcher::with_options(data[..data.len() - 4].to_vec(), common::TEST_KEY, options).unwrap();
+    assert_eq!(searcher.search("223.5.5.5").unwrap_err().code(), "TRUNCATED");
+    assert_eq!(searcher.search("8.8.8.8").unwrap(), "美国");
+}
+
+/// Test the LRU result cache: range-keyed hits, eviction, thread safety and reload
+#[test]
+fn test_result_cache() {
+    let options = SearchOptions { cache_capacity: 2, ..SearchOptions::default() };
+    let searcher = DbSearcher::with_options(common::sample_v4(), common::TEST_KEY, options.clone()).unwrap();
+    assert_eq!(searcher.options(), options);
+    let stats = |s: &DbSearcher| {
+        let stats = s.cache_stats().expect("Cache disabled");
+        (stats.hits, stats.misses, stats.entries)
+    };
+
+    // Any address of a cached range is a hit
+    assert_eq!(searcher.search("1.0.1.1").unwrap(), "中国–福建\t电信");
+    assert_eq!(searcher.search("1.0.1.200").unwrap(), "中国–福建\t电信");
+    let record = searcher.search_record("1.0.1.9").unwrap().unwrap();
+    assert_eq!(record.start, "1.0.1.0".parse::<IpAddr>().unwrap());
+    assert_eq!(record.end, "1.0.1.255".parse::<IpAddr>().unwrap());
+    assert_eq!(stats(&searcher), (2, 1, 1));
+
+    // Gaps are not cached
+    assert_eq!(searcher.search("1.0.4.1").unwrap(), "Unknown");
+    assert_eq!(searcher.search_shared("1.0.4.1").unwrap(), None);
+    assert_eq!(stats(&searcher), (2, 3, 1));
+    assert_eq!(searcher.search("2001:da8::1").unwrap_err().code(), "IP_TYPE_MISMATCH");
+
+    // 8.8.8.0/24 is more recently used than 1.0.1.0/24 when 223.5.5.0/24 arrives
+    searcher.search("1.0.1.1").unwrap();
+    searcher.search("8.8.8.8").unwrap();
+    searcher.search("223.5.5.5").unwrap();
+    assert_eq!(stats(&searcher), (3, 5, 2));
+    let mut out = String::new();
+    assert!(searcher.search_into("8.8.8.1", &mut out).unwrap());
+    assert_eq!(out, "美国");
+    assert_eq!(stats(&searcher), (4, 5, 2));
+    searcher.search("1.0.1.1").unwrap();
+    assert_eq!(stats(&searcher), (4, 6, 2));
+
+    searcher.clear_cache();
+    assert_eq!(stats(&searcher), (0, 0, 0));
+
+    // Shared between threads
+    let shared = std::sync::Arc::new(DbSearcher::with_options(common::sample_v4(), common::TEST_KEY, options).unwrap());
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let searcher = std::sync::Arc::clone(&shared);
+            std::thread::spawn(move || {
+                for _ in 0..100 {
+                    for (start, _, region) in common::SAMPLE_V4 {
+                        assert_eq!(searcher.search(start).unwrap(), *region);
+                    }
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    let (hits, misses, entries) = stats(&shared);
+    assert_eq!(hits + misses, 4 * 100 * common::SAMPLE_V4.len() as u64);
+    assert_eq!(entries, 2);
+
+    // Reloading drops results of the old database
+    let mut searcher = DbSearcher::with_options(common::sample_v4(), common::TEST_KEY, SearchOptions {
+        cache_capacity: 16,
+        ..SearchOptions::default()
+    })
+    .unwrap();
+    assert_eq!(searcher.search("8.8.8.8").unwrap(), "美国");
+    let mut ranges = common::SAMPLE_V4.to_vec();
+    ranges[5] = ("8.8.8.0", "8.8.8.255", "美国\tGoogle");
+    searcher.reload(common::build_db(&ranges), common::TEST_KEY).unwrap();
+    assert_eq!(stats(&searcher), (0, 0, 0));
+    assert_eq!(searcher.search("8.8.8.8").unwrap(), "美国\tGoogle");
+    assert_eq!(searcher.options().cache_capacity, 16);
+
+    // A failed reload keeps the current database
+    assert!(searcher.reload(common::sample_v4()[..20].to_vec(), common::TEST_KEY).is_err());
+    assert_eq!(searcher.search("8.8.8.8").unwrap(), "美国\tGoogle");
+
+    assert_eq!(DbSearcher::new(common::sample_v4(), common::TEST_KEY).unwrap().cache_stats(), None);
+}
+
+/// Test GBK databases decode through detection or an explicit encoding, and strict mode rejects bad text
+#[test]
+fn test_text_encoding() {
+    let gbk = |text: &str| {
+        let (bytes, _, unmappable) = encoding_rs::GBK.encode(text);
+        assert!(!unmappable, "{} has no GBK encoding", text);
+        bytes.into_owned()
+    };
+    let data = common::build_db_encoded(common::SAMPLE_V4, gbk);
+    let with = |encoding, strict_encoding| {
+        let options = SearchOptions { encoding, strict_encoding, ..SearchOptions::default() };
+        DbSearcher::with_options(data.clone(), common::TEST_KEY, options).expect("Failed to init searcher")
+    };
+
+    // Detected, or given explicitly
+    for (encoding, resolved) in [
+        (TextEncoding::Auto, TextEncoding::Gb18030),
+        (TextEncoding::Gbk, TextEncoding::Gbk),
+        (TextEncoding::Gb18030, TextEncoding::Gb18030),
+    ] {
+        let searcher = with(encoding, true);
+        assert_eq!(searcher.encoding(), resolved);
+        // Options keep `Auto`, so a reload detects again
+        assert_eq!(searcher.options().encoding, encoding);
+        for (start, _, region) in common::SAMPLE_V4 {
+            assert_eq!(searcher.search(start).unwrap(), *region);
+        }
+        assert!(searcher.verify().is_ok());
+    }
+
+    // Read as UTF-8: replacement characters, or an error in strict mode
+    let lossy = with(TextEncoding::Utf8, false).search("1.0.1.1").unwrap();
+    assert!(lossy.contains('\u{FFFD}'));
+    let err = with(TextEncoding::Utf8, true).search("1.0.1.1").unwrap_err();
+    assert!(matches!(err, CzdbError::InvalidText { section: Section::GeoMap, encoding: TextEncoding::Utf8, .. }));
+    assert_eq!(err.code(), "INVALID_TEXT");
+    let report = with(TextEncoding::Utf8, false).verify();
+    assert!(report.issues.iter().any(|issue| issue.check == VerifyCheck::RegionDecode));
+
+    // UTF-8 databases are detected as such
+    let searcher = DbSearcher::new(common::sample_v4(), common::TEST_KEY).unwrap();
+    assert_eq!(searcher.encoding(), TextEncoding::Utf8);
+
+    // GBK text only in the trailing strings, with no geo map, is detected too
+    let data = common::build_db_trailing(common::SAMPLE_V4, gbk);
+    let searcher = DbSearcher::new(data.clone(), common::TEST_KEY).unwrap();
+    assert_eq!(searcher.encoding(), TextEncoding::Gb18030);
+    for (start, _, region) in common::SAMPLE_V4 {
+        assert_eq!(searcher.search(start).unwrap(), *region);
+    }
+    let options = SearchOptions { strict_encoding: true, mode: SearchMode::BTree, ..SearchOptions::default() };
+    let searcher = DbSearcher::with_options(data, common::TEST_KEY, options).unwrap();
+    assert_eq!(searcher.encoding(), TextEncoding::Gb18030);
+    assert!(searcher.verify().is_ok());
+    let data = common::build_db_trailing(common::SAMPLE_V4, |text| text.as_bytes().to_vec());
+    let searcher = DbSearcher::new(data, common::TEST_KEY).unwrap();
+    assert_eq!(searcher.encoding(), TextEncoding::Utf8);
+    assert_eq!(searcher.search("1.0.1.1").unwrap(), common::SAMPLE_V4[0].2);
+}
+
+/// Test parsing region text into a location hierarchy
+#[test]
+fn test_location_parse() {
+    let location = |area: Option<&str>, levels: [Option<&str>; 4], division, isp: Option<&str>| Location {
+        area: area.map(str::to_string),
+        country: levels[0].map(str::to_string),
+        province: levels[1].map(str::to_string),
+        division,
+        city: levels[2].map(str::to_string),
+        district: levels[3].map(str::to_string),
+        isp: isp.map(str::to_string),
+        ..Location::default()
+    };
+    let province = Some(DivisionKind::Province);
+    let municipality = Some(DivisionKind::Municipality);
+
+    let cases = [
+        ("中国–福建\t电信", location(None, [Some("中国"), Some("福建"), None, None], province, Some("电信"))),
+        ("中国–北京–北京–海淀区\t北龙中网(北京)科技有限公司", location(None, [Some("中国"), Some("北京"), Some("北京"), Some("海淀区")], municipality, Some("北龙中网(北京)科技有限公司"))),
+        ("中国–上海–浦东新区", location(None, [Some("中国"), Some("上海"), Some("上海"), Some("浦东新区")], municipality, None)),
+        ("中国–内蒙古–呼和浩特\t电信", location(None, [Some("中国"), Some("内蒙古"), Some("呼和浩特"), None], Some(DivisionKind::AutonomousRegion), Some("电信"))),
+        ("中国–广西壮族自治区–南宁市", location(None, [Some("中国"), Some("广西"), Some("南宁市"), None], Some(DivisionKind::AutonomousRegion), None)),
+        ("中国–香港\tCloudie_Limited", location(None, [Some("中国"), Some("香港"), None, None], Some(DivisionKind::SpecialAdministrativeRegion), Some("Cloudie_Limited"))),
+        ("中国\t教育网", location(None, [Some("中国"), None, None, None], None, Some("教育网"))),
+        ("日本–大阪府–大阪市\tCat_Networks", location(None, [Some("日本"), Some("大阪府"), Some("大阪市"), None], None, Some("Cat_Networks"))),
+        ("美国", location(None, [Some("美国"), None, None, None], None, None)),
+        ("亚太地区", location(Some("亚太地区"), [None; 4], None, None)),
+        ("局域网\t对方和您在同一内部网", location(Some("局域网"), [None; 4], None, Some("对方和您在同一内部网"))),
+        // Older concatenated text with a space before the ISP
+        ("福建省福州市鼓楼区 电信", location(None, [Some("中国"), Some("福建"), Some("福州市"), Some("鼓楼区")], province, Some("电信"))),
+        ("中国–宁夏中卫\t联通", location(None, [Some("中国"), Some("宁夏"), Some("中卫"), None], Some(DivisionKind::AutonomousRegion), Some("联通"))),
+        ("中国澳门特别行政区", location(None, [Some("中国"), Some("澳门"), None, None], Some(DivisionKind::SpecialAdministrativeRegion), None)),
+        ("北京市朝阳区 联通", location(None, [Some("中国"), Some("北京"), Some("北京"), Some("朝阳区")], municipality, Some("联通"))),
+        ("中国-福建-福州 电信", location(None, [Some("中国"), Some("福建"), Some("福州"), None], province, Some("电信"))),
+        // Hyphens and spaces inside foreign names
+        ("美国–纽约州–New York", location(None, [Some("美国"), Some("纽约州"), Some("New York"), None], None, None)),
+        ("美国–Winston-Salem\tX", location(None, [Some("美国"), Some("Winston-Salem"), None, None], None, Some("X"))),
+        ("United States", location(None, [Some("United States"), None, None, None], None, None)),
+        ("", Location::default()),
+    ];
+    for (region, expected) in cases {
+        // Codes are covered by test_admin_division and test_iso_codes, the
+        // carrier by test_isp_normalization
+        let parsed = Location {
+            province_code: None,
+            city_code: None,
+            country_code: None,
+            country_alpha3: None,
+            subdivision_code: None,
+            carrier: None,
+            isp_category: IspCategory::Unknown,
+            ..Location::parse(region)
+        };
+        assert_eq!(parsed, expected, "{:?}", region);
+    }
+}
+
+/// Test the structured lookup against the plain one
+#[test]
+fn test_search_location() {
+    for options in [SearchOptions::default(), SearchOptions { mode: SearchMode::BTree, cache_capacity: 4, ..SearchOptions::default() }] {
+        let searcher = DbSearcher::with_options(common::sample_v4(), common::TEST_KEY, options).unwrap();
+        for (start, _, region) in common::SAMPLE_V4 {
+            assert_eq!(searcher.search_location(start).unwrap(), Some(Location::parse(region)));
+        }
+        let location = searcher.search_location("202.96.128.1").unwrap().unwrap();
+        assert_eq!(location.province.as_deref(), Some("广东"));
+        assert_eq!(location.city.as_deref(), Some("广州"));
+        assert_eq!(location.isp.as_deref(), Some("电信"));
+        assert_eq!(searcher.search_location("1.0.4.1").unwrap(), None);
+        assert!(searcher.search_location("::1").is_err());
+    }
+}
+
+/// Test GB/T 2260 codes: name and code lookups, former prefectures, parsed locations
+#[test]
+fn test_admin_division() {
+    let code = |province, city| AdminDivision::city(province, city).map(|d| d.code);
+    assert_eq!(AdminDivision::province("广东").unwrap(), AdminDivision { code: 440000, name: "广东省" });
+    assert_eq!(AdminDivision::province("广西壮族自治区").map(|d| d.code), Some(450000));
+    assert_eq!(AdminDivision::province("加利福尼亚州"), None);
+
+    assert_eq!(code("广东", "广州"), Some(440100));
+    assert_eq!(code("广东省", "广州市"), Some(440100));
+    assert_eq!(code("吉林", "延边"), Some(222400));
+    assert_eq!(code("吉林", "延边州"), Some(222400));
+    assert_eq!(code("湖北", "恩施土家族苗族自治州"), Some(422800));
+    assert_eq!(code("黑龙江", "大兴安岭"), Some(232700));
+    assert_eq!(code("内蒙古", "阿拉善"), Some(152900));
+    assert_eq!(code("北京", "北京"), Some(110100));
+    assert_eq!(code("广东", "南京"), None);
+    // Renamed and merged prefectures resolve to the current city
+    assert_eq!(code("湖北", "襄樊"), Some(420600));
+    assert_eq!(code("山东", "莱芜"), Some(370100));
+    assert_eq!(code("贵州", "毕节地区"), Some(520500));
+
+    let guangzhou = AdminDivision::from_code(440100).unwrap();
+    assert_eq!(guangzhou.name, "广州市");
+    assert_eq!(guangzhou.parent().map(|d| d.name), Some("广东省"));
+    assert_eq!(AdminDivision::from_code(440000).unwrap().parent(), None);
+    assert_eq!(AdminDivision::from_code(110100).map(|d| d.name), Some("北京市"));
+    assert_eq!(AdminDivision::from_code(371200).map(|d| (d.code, d.name)), Some((370100, "济南市")));
+    assert_eq!(AdminDivision::from_code(999999), None);
+    assert_eq!(AdminDivision::from_code(440199), None);
+
+    let location = Location::parse("中国–广东–广州\t电信");
+    assert_eq!((location.province_code, location.city_code), (Some(440000), Some(440100)));
+    let location = Location::parse("中国–北京–海淀区");
+    assert_eq!((location.province_code, location.city_code), (Some(110000), Some(110100)));
+    // 重庆's counties are grouped under 500200, its urban districts under 500100
+    let location = Location::parse("中国–重庆–城口县\t电信");
+    assert_eq!((location.province_code, location.city_code), (Some(500000), Some(500200)));
+    let location = Location::parse("中国–重庆–重庆–石柱土家族自治县");
+    assert_eq!(location.city_code, Some(500200));
+    assert_eq!(location.localized(Language::English).city.as_deref(), Some("Chongqing"));
+    let location = Location::parse("中国–重庆–渝中区");
+    assert_eq!(location.city_code, Some(500100));
+    assert_eq!(AdminDivision::from_code(500200).map(|d| d.name), Some("重庆市"));
+    let location = Location::parse("中国–香港\tCloudie_Limited");
+    assert_eq!((location.province_code, location.city_code), (Some(810000), None));
+    let location = Location::parse("日本–大阪府–大阪市");
+    assert_eq!((location.province_code, location.city_code), (None, None));
+
+    let searcher = DbSearcher::new(common::sample_v4(), common::TEST_KEY).unwrap();
+    let location = searcher.search_location("223.5.5.5").unwrap().unwrap();
+    assert_eq!(location.city_code.and_then(AdminDivision::from_code).map(|d| d.name), Some("杭州市"));
+}
+
+/// Test ISO 3166-1 country and ISO 3166-2:CN subdivision codes
+#[test]
+fn test_iso_codes() {
+    let us = Country::from_name("美国").unwrap();
+    assert_eq!((us.alpha2, us.alpha3), ("US", "USA"));
+    assert_eq!(Country::from_code("usa"), Some(us));
+    assert_eq!(Country::from_code("US"), Some(us));
+    assert_eq!(Country::from_name("大韩民国").map(|c| c.alpha3), Some("KOR"));
+    assert_eq!(Country::from_name("亚太地区"), None);
+    assert_eq!(Country::from_name("US"), None);
+    assert_eq!(Country::from_code("XX"), None);
+
+    let codes = |region| {
+        let location = Location::parse(region);
+        (location.country_code, location.country_alpha3, location.subdivision_code)
+    };
+    assert_eq!(codes("中国–广东–广州\t电信"), (Some("CN"), Some("CHN"), Some("CN-GD")));
+    assert_eq!(codes("中国–香港"), (Some("CN"), Some("CHN"), Some("CN-HK")));
+    assert_eq!(codes("中国\t教育网"), (Some("CN"), Some("CHN"), None));
+    assert_eq!(codes("日本–大阪府–大阪市"), (Some("JP"), Some("JPN"), None));
+    assert_eq!(codes("亚太地区"), (None, None, None));
+
+    let searcher = DbSearcher::new(common::sample_v6(), common::TEST_KEY).unwrap();
+    let location = searcher.search_location("2001:4860::8888").unwrap().unwrap();
+    assert_eq!((location.country_code, location.isp.as_deref()), (Some("US"), Some("Google")));
+}
+
+/// Test localized place names: language tags, tables, fallbacks and the searcher option
+#[test]
+fn test_localization() {
+    assert_eq!("en-US".parse::<Language>().unwrap(), Language::English);
+    assert_eq!("zh_TW".parse::<Language>().unwrap(), Language::TraditionalChinese);
+    assert_eq!("Pinyin".parse::<Language>().unwrap(), Language::Pinyin);
+    assert_eq!("zh".parse::<Language>().unwrap(), Language::Chinese);
+    assert_eq!("fr".parse::<Language>().unwrap_err().code(), "INVALID_OPTION");
+    assert_eq!(Language::TraditionalChinese.to_string(), "zh-Hant");
+
+    let names = |region, language| {
+        let location = Location::parse(region).localized(language);
+        (location.country.or(location.area), location.province, location.city)
+    };
+    let owned = |country: &str, province: Option<&str>, city: Option<&str>| {
+        (Some(country.to_string()), province.map(str::to_string), city.map(str::to_string))
+    };
+    let cases = [
+        ("中国–内蒙古–呼和浩特\t电信", Language::English, owned("China", Some("Inner Mongolia"), Some("Hohhot"))),
+        ("中国–内蒙古–呼和浩特\t电信", Language::Pinyin, owned("Zhongguo", Some("Neimenggu"), Some("Huhehaote"))),
+        ("中国–陕西–西安\t电信", Language::Pinyin, owned("Zhongguo", Some("Shaanxi"), Some("Xi'an"))),
+        ("中国–北京–海淀区", Language::English, owned("China", Some("Beijing"), Some("Beijing"))),
+        ("中国–香港", Language::English, owned("China", Some("Hong Kong"), None)),
+        ("中国–辽宁–沈阳\t联通", Language::TraditionalChinese, owned("中國", Some("遼寧"), Some("瀋陽"))),
+        ("日本–大阪府–大阪市", Language::English, owned("Japan", Some("大阪府"), Some("大阪市"))),
+        ("美国", Language::Pinyin, owned("United States", None, None)),
+        ("亚太地区", Language::English, owned("Asia Pacific", None, None)),
+        ("中国–台湾–台北市", Language::English, owned("China", Some("Taiwan"), Some("台北市"))),
+    ];
+    for (region, language, expected) in cases {
+        assert_eq!(names(region, language), expected, "{region} in {language}");
+    }
+
+    // The ISP and the codes are untouched
+    let location = Location::parse("中国–广东–广州–天河区\t电信");
+    let traditional = location.clone().localized(Language::TraditionalChinese);
+    assert_eq!(traditional.district.as_deref(), Some("天河區"));
+    assert_eq!(traditional.isp, location.isp);
+    assert_eq!((traditional.city_code, traditional.subdivision_code), (location.city_code, location.subdivision_code));
+    assert_eq!(location.clone().localized(Language::Chinese), location);
+
+    let options = SearchOptions { language: Language::English, cache_capacity: 4, ..SearchOptions::default() };
+    let mut searcher = DbSearcher::with_options(common::sample_v4(), common::TEST_KEY, options).unwrap();
+    assert_eq!(searcher.options().language, Language::English);
+    assert_eq!(searcher.search("202.96.128.1").unwrap(), "中国–广东–广州\t电信");
+    let location = searcher.search_location("202.96.128.1").unwrap().unwrap();
+    assert_eq!((location.province.as_deref(), location.city.as_deref()), (Some("Guangdong"), Some("Guangzhou")));
+    let location = searcher.search_location_in("202.96.128.1", Language::Chinese).unwrap().unwrap();
+    assert_eq!(location.city.as_deref(), Some("广州"));
+
+    searcher.set_language(Language::TraditionalChinese);
+    assert_eq!(searcher.language(), Language::TraditionalChinese);
+    let location = searcher.search_location("202.96.128.1").unwrap().unwrap();
+    assert_eq!(location.province.as_deref(), Some("廣東"));
+}
+
+/// Test ISP normalization: carrier ids, categories and keyword precedence
+#[test]
+fn test_isp_normalization() {
+    let normalize = |isp| (Carrier::from_isp(isp).map(|c| c.id), IspCategory::classify(isp));
+    let cases = [
+        ("电信", Some("chinatelecom"), IspCategory::Residential),
+        ("中国联通", Some("chinaunicom"), IspCategory::Residential),
+        ("移动", Some("chinamobile"), IspCategory::Mobile),
+        ("中移铁通", Some("chinamobile"), IspCategory::Residential),
+        ("联通/GSM/WCDMA/LTE共用出口", Some("chinaunicom"), IspCategory::Mobile),
+        ("电信/数据上网公共出口", Some("chinatelecom"), IspCategory::Mobile),
+        ("联通/IDC机房", Some("chinaunicom"), IspCategory::Datacenter),
+        ("电信天翼云计算数据中心", Some("ctyun"), IspCategory::Datacenter),
+        ("阿里云", Some("aliyun"), IspCategory::Datacenter),
+        ("腾讯公司电信数据中心", Some("tencentcloud"), IspCategory::Datacenter),
+        ("Google", Some("google"), IspCategory::Datacenter),
+        ("Microsoft-BYOIP", Some("microsoft"), IspCategory::Datacenter),
+        ("联通/鹏博士宽带", Some("drpeng"), IspCategory::Residential),
+        ("歌华有线", Some("chinabroadnet"), IspCategory::Residential),
+        ("教育网/北京大学", Some("cernet"), IspCategory::Education),
+        ("科技网", Some("cstnet"), IspCategory::Education),
+        ("复旦大学", None, IspCategory::Education),
+        ("方正宽带", None, IspCategory::Residential),
+        ("BGP多线", None, IspCategory::Datacenter),
+        // `网通` and `科技网` do not match inside `网通信` / `科技网络`
+        ("北京宽捷网通信技术有限公司", None, IspCategory::Unknown),
+        ("上海众生网络科技网络有限公司", None, IspCategory::Unknown),
+        ("APNIC", None, IspCategory::Unknown),
+        // ASCII keywords match whole words only
+        ("AWS", Some("aws"), IspCategory::Datacenter),
+        ("OVH SAS", Some("ovh"), IspCategory::Datacenter),
+        ("Dawson", None, IspCategory::Unknown),
+        ("Covhost", None, IspCategory::Unknown),
+        ("Lidcom", None, IspCategory::Unknown),
+        ("Cdnow Vpsnet", None, IspCategory::Unknown),
+    ];
+    for (isp, carrier, category) in cases {
+        assert_eq!(normalize(isp), (carrier, category), "{isp}");
+    }
+
+    let telecom = Carrier::from_id("chinatelecom").unwrap();
+    assert_eq!((telecom.name, telecom.category), ("中国电信", IspCategory::Residential));
+    assert_eq!(Carrier::from_id("nope"), None);
+    assert_eq!(IspCategory::Datacenter.to_string(), "datacenter");
+
+    let searcher = DbSearcher::new(common::sample_v4(), common::TEST_KEY).unwrap();
+    let location = searcher.search_location("223.5.5.5").unwrap().unwrap();
+    assert_eq!((location.carrier, location.isp_category), (Carrier::from_id("aliyun"), IspCategory::Datacenter));
+    let location = searcher.search_location("8.8.8.8").unwrap().unwrap();
+    assert_eq!((location.carrier, location.isp_category), (None, IspCategory::Unknown));
+}
+
+/// Test listing geo rows and trailing strings with their coverage
+#[test]
+fn test_region_dictionary() {
+    let searcher = DbSearcher::new(common::sample_v4(), common::TEST_KEY).unwrap();
+    let dictionary = searcher.region_dictionary().unwrap();
+
+    // The fixture stores one geo row per distinct region and an empty trailing string
+    let mut regions: Vec<&str> = common::SAMPLE_V4.iter().map(|(_, _, region)| *region).collect();
+    regions.sort();
+    regions.dedup();
+    assert_eq!(dictionary.geo_rows.len(), regions.len());
+    for (id, row) in dictionary.geo_rows.iter().enumerate() {
+        assert_eq!(row.id as usize, id);
+        assert_eq!(row.columns.join("\t"), row.text);
+        let ranges: Vec<_> = common::SAMPLE_V4.iter().filter(|(_, _, region)| *region == row.text).collect();
+        assert_eq!(row.ranges, ranges.len(), "{}", row.text);
+    }
+
+    let fujian = dictionary.geo_rows.iter().find(|row| row.text == "中国–福建\t电信").unwrap();
+    assert_eq!(fujian.columns, ["中国–福建", "电信"]);
+    assert_eq!((fujian.ranges, fujian.addresses), (3, 256 + 512 + 256));
+    let japan = dictionary.geo_rows.iter().find(|row| row.text == "日本").unwrap();
+    assert_eq!((japan.ranges, japan.addresses), (1, 1 << 20));
+
+    assert_eq!(dictionary.strings.len(), 1);
+    let string = &dictionary.strings[0];
+    assert_eq!((string.id, string.text.as_str(), string.ranges), (0, "", common::SAMPLE_V4.len()));
+    let total: u128 = dictionary.geo_rows.iter().map(|row| row.addresses).sum();
+    assert_eq!(string.addresses, total);
+
+    // A range ending before it starts is reported, not counted
+    let reversed = common::build_db(&[("1.0.0.0", "1.0.0.255", "美国"), ("1.0.2.0", "1.0.1.0", "日本")]);
+    let searcher = DbSearcher::new(reversed, common::TEST_KEY).unwrap();
+    let err = searcher.region_dictionary().unwrap_err();
+    assert!(matches!(err, CzdbError::InvalidFormat { section: Section::Index, .. }), "{err}");
+
+    let searcher = DbSearcher::new(common::sample_v6(), common::TEST_KEY).unwrap();
+    let dictionary = searcher.region_dictionary().unwrap();
+    let ranges: usize = dictionary.geo_rows.iter().map(|row| row.ranges).sum();
+    assert_eq!(ranges, common::SAMPLE_V6.len());
+}
+
+/// Test reverse lookup of ranges and CIDR blocks by region
+#[test]
+fn test_search_region() {
+    let searcher = DbSearcher::new(common::sample_v4(), common::TEST_KEY).unwrap();
+    let filter = |country: &str, province: Option<&str>, isp: Option<&str>| RegionFilter {
+        country: Some(country.to_string()),
+        province: province.map(str::to_string),
+        isp: isp.map(str::to_string),
+        ..Default::default()
+    };
+    let starts = |filter: &RegionFilter| -> Vec<String> {
+        searcher.search_region(filter).unwrap().iter().map(|range| range.start.to_string()).collect()
+    };
+    let cidrs = |filter: &RegionFilter| -> Vec<String> {
+        searcher.region_cidrs(filter).unwrap().iter().map(|net| net.to_string()).collect()
+    };
+
+    let fujian = filter("中国", Some("福建"), Some("电信"));
+    let ranges = searcher.search_region(&fujian).unwrap();
+    assert_eq!(ranges.len(), 3);
+    assert_eq!((ranges[1].start.to_string(), ranges[1].end.to_string()), ("1.0.2.0".to_string(), "1.0.3.255".to_string()));
+    assert!(ranges.iter().all(|range| &*range.region == "中国–福建\t电信"));
+    // 1.0.1.0/24 and 1.0.2.0/23 are adjacent but do not form a /22
+    assert_eq!(cidrs(&fujian), ["1.0.1.0/24", "1.0.2.0/23", "1.1.0.0/24"]);
+
+    assert_eq!(starts(&filter("中国", Some("广东"), None)), ["1.0.8.0", "1.0.32.0", "202.96.128.0"]);
+    let guangzhou = RegionFilter { city: Some("广州".to_string()), ..Default::default() };
+    assert_eq!(cidrs(&guangzhou), ["202.96.128.0/24"]);
+    assert_eq!(starts(&filter("亚太地区", None, None)), ["36.50.226.0"]);
+    assert!(starts(&filter("中国", Some("福"), None)).is_empty());
+
+    // Full names of the country and of province-level divisions
+    assert_eq!(starts(&filter("中华人民共和国", Some("浙江省"), None)), ["223.5.5.0"]);
+    assert_eq!(starts(&filter("中国", Some("广东省"), Some("电信"))).len(), 3);
+    let guangxi = common::build_db(&[("1.0.0.0", "1.0.0.255", "中国–广西壮族自治区–南宁\t联通")]);
+    let guangxi = DbSearcher::new(guangxi, common::TEST_KEY).unwrap();
+    for province in ["广西", "广西壮族自治区"] {
+        let filter = RegionFilter { province: Some(province.to_string()), ..Default::default() };
+        assert_eq!(guangxi.search_region(&filter).unwrap().len(), 1, "{province}");
+    }
+
+    // Prefix matching
+    let prefix = RegionFilter { province: Some("福".to_string()), matching: RegionMatch::Prefix, ..Default::default() };
+    assert_eq!(starts(&prefix).len(), 3);
+    let aliyun = RegionFilter { isp: Some("阿里".to_string()), matching: RegionMatch::Prefix, ..Default::default() };
+    assert_eq!(cidrs(&aliyun), ["223.5.5.0/24"]);
+
+    // The default filter matches every range
+    assert_eq!(searcher.search_region(&RegionFilter::default()).unwrap().len(), common::SAMPLE_V4.len());
+
+    // Adjacent ranges of different regions merge; unaligned ranges split into the fewest blocks
+    let ranges = [
+        ("10.0.0.1", "10.0.0.6", "中国–北京\t联通"),
+        ("10.0.0.7", "10.0.0.7", "中国–北京\t电信"),
+        ("10.0.0.8", "10.0.0.15", "中国–北京\t联通"),
+        ("10.0.1.0", "10.0.1.255", "美国"),
+    ];
+    let searcher = DbSearcher::with_mode(common::build_db(&ranges), common::TEST_KEY, SearchMode::BTree).unwrap();
+    let beijing = RegionFilter { province: Some("北京".to_string()), ..Default::default() };
+    let blocks: Vec<String> = searcher.region_cidrs(&beijing).unwrap().iter().map(|net| net.to_string()).collect();
+    assert_eq!(blocks, ["10.0.0.1/32", "10.0.0.2/31", "10.0.0.4/30", "10.0.0.8/29"]);
+    let unicom = RegionFilter { isp: Some("联通".to_string()), ..Default::default() };
+    let blocks: Vec<String> = searcher.region_cidrs(&unicom).unwrap().iter().map(|net| net.to_string()).collect();
+    assert_eq!(blocks, ["10.0.0.1/32", "10.0.0.2/31", "10.0.0.4/31", "10.0.0.6/32", "10.0.0.8/29"]);
+
+    // A range ending before it starts fails the lookup instead of reaching the CIDR split
+    let reversed = common::build_db(&[("1.0.0.0", "1.0.0.255", "美国"), ("1.0.2.0", "1.0.1.0", "日本")]);
+    let searcher = DbSearcher::new(reversed, common::TEST_KEY).unwrap();
+    let err = searcher.region_cidrs(&RegionFilter::default()).unwrap_err();
+    assert!(matches!(err, CzdbError::InvalidFormat { section: Section::Index, .. }), "{err}");
+    assert!(searcher.search_region(&beijing).is_err());
+
+    let searcher = DbSearcher::new(common::build_db(&[("::", "ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff", "保留地址")]), common::TEST_KEY).unwrap();
+    let all: Vec<String> = searcher.region_cidrs(&RegionFilter::default()).unwrap().iter().map(|net| net.to_string()).collect();
+    assert_eq!(all, ["::/0"]);
+}
+
+/// Test firewall and routing list generation
+#[test]
+fn test_region_list() {
+    let ranges = [
+        ("1.0.0.0", "1.0.0.255", "中国–福建\t电信"),
+        ("1.0.1.0", "1.0.1.255", "中国–广东\t电信"),
+        ("1.0.4.0", "1.0.7.255", "美国"),
+        ("1.0.8.0", "1.0.8.127", "中国–福建\t联通"),
+    ];
+    let searcher = DbSearcher::new(common::build_db(&ranges), common::TEST_KEY).unwrap();
+    let china = RegionFilter { country: Some("中国".to_string()), ..Default::default() };
+    let list = |format: ListFormat| searcher.region_list(&china, &ListOptions { format, ..Default::default() }).unwrap();
+
+    assert_eq!(list(ListFormat::Cidr), "1.0.0.0/23\n1.0.8.0/25\n");
+    assert_eq!(
+        list(ListFormat::Nftables),
+        "table inet czdb {\n\tset czdb_v4 {\n\t\ttype ipv4_addr\n\t\tflags interval\n\t\telements = {\n\t\t\t1.0.0.0/23,\n\t\t\t1.0.8.0/25\n\t\t}\n\t}\n}\n"
+    );
+    assert_eq!(
+        list(ListFormat::Ipset),
+        "create czdb_v4 hash:net family inet hashsize 1024 maxelem 65536 -exist\nflush czdb_v4\nadd czdb_v4 1.0.0.0/23\nadd czdb_v4 1.0.8.0/25\n"
+    );
+    assert_eq!(
+        list(ListFormat::Iptables),
+        "*filter\n:czdb_v4 - [0:0]\n-A czdb_v4 -s 1.0.0.0/23 -j DROP\n-A czdb_v4 -s 1.0.8.0/25 -j DROP\nCOMMIT\n"
+    );
+    assert_eq!(list(ListFormat::Bird), "protocol static czdb_v4 {\n\troute 1.0.0.0/23 unreachable;\n\troute 1.0.8.0/25 unreachable;\n}\n");
+    assert!(list(ListFormat::Bird2).starts_with("protocol static czdb_v4 {\n\tipv4;\n\troute 1.0.0.0/23 unreachable;\n"));
+
+    // Everything outside China, including addresses missing from the database
+    let options = ListOptions { invert: true, ..Default::default() };
+    let rest = searcher.region_list(&china, &options).unwrap();
+    let blocks: Vec<&str> = rest.lines().collect();
+    assert_eq!(&blocks[..3], ["0.0.0.0/8", "1.0.2.0/23", "1.0.4.0/22"]);
+    assert!(blocks.contains(&"1.0.8.128/25") && blocks.contains(&"128.0.0.0/1"));
+    let covered: u64 = blocks.iter().map(|block| 1u64 << (32 - block.split_once('/').unwrap().1.parse::<u32>().unwrap())).sum();
+    assert_eq!(covered, (1u64 << 32) - 512 - 128);
+
+    let options = ListOptions {
+        format: ListFormat::Iptables,
+        name: Some("CHINA".to_string()),
+        target: "ACCEPT".to_string(),
+        destination: true,
+        ..Default::default()
+    };
+    assert!(searcher.region_list(&china, &options).unwrap().contains("-A CHINA -d 1.0.0.0/23 -j ACCEPT\n"));
+    let nothing = RegionFilter { country: Some("日本".to_string()), ..Default::default() };
+    let options = ListOptions { format: ListFormat::Nftables, ..Default::default() };
+    assert!(!searcher.region_list(&nothing, &options).unwrap().contains("elements"));
+
+    let searcher = DbSearcher::new(common::sample_v6(), common::TEST_KEY).unwrap();
+    let options = ListOptions { format: ListFormat::Bird2, route: "via \"eth1\"".to_string(), ..Default::default() };
+    let bird = searcher.region_list(&RegionFilter::default(), &options).unwrap();
+    assert!(bird.starts_with("protocol static czdb_v6 {\n\tipv6;\n\troute "));
+    assert!(bird.contains(" via \"eth1\";\n"));
+    let options = ListOptions { format: ListFormat::Ipset, invert: true, ..Default::default() };
+    assert!(searcher.region_list(&RegionFilter::default(), &options).unwrap().contains("family inet6"));
+
+    // hash:net rejects /0, so a list covering every address is added in halves
+    let everything = common::build_db(&[("0.0.0.0", "255.255.255.255", "保留地址")]);
+    let searcher = DbSearcher::new(everything, common::TEST_KEY).unwrap();
+    let ipset = searcher.region_list(&RegionFilter::default(), &ListOptions { format: ListFormat::Ipset, ..Default::default() }).unwrap();
+    assert!(ipset.ends_with("flush czdb_v4\nadd czdb_v4 0.0.0.0/1\nadd czdb_v4 128.0.0.0/1\n"), "{ipset}");
+    let nothing = RegionFilter { country: Some("美国".to_string()), ..Default::default() };
+    let options = ListOptions { format: ListFormat::Ipset, invert: true, ..Default::default() };
+    assert!(searcher.region_list(&nothing, &options).unwrap().ends_with("add czdb_v4 0.0.0.0/1\nadd czdb_v4 128.0.0.0/1\n"));
+    let cidr = searcher.region_list(&RegionFilter::default(), &ListOptions::default()).unwrap();
+    assert_eq!(cidr, "0.0.0.0/0\n");
+    let v6 = DbSearcher::new(common::sample_v6(), common::TEST_KEY).unwrap();
+    let ipset = v6.region_list(&RegionFilter { country: Some("火星".to_string()), ..Default::default() }, &options).unwrap();
+    assert!(ipset.ends_with("add czdb_v6 ::/1\nadd czdb_v6 8000::/1\n"), "{ipset}");
+
+    assert_eq!("nft".parse::<ListFormat>().unwrap(), ListFormat::Nftables);
+    assert_eq!("BIRD2".parse::<ListFormat>().unwrap().to_string(), "bird2");
+    assert_eq!("pf".parse::<ListFormat>().unwrap_err().code(), "INVALID_OPTION");
+}
+
+/// Test prefix and range queries returning overlapping ranges
+#[test]
+fn test_search_prefix() {
+    for mode in [SearchMode::Memory, SearchMode::BTree] {
+        let searcher = DbSearcher::with_mode(common::sample_v4(), common::TEST_KEY, mode).unwrap();
+        let summary = |overlaps: Vec<RangeOverlap>| -> Vec<(String, String, u128)> {
+            overlaps.into_iter().map(|o| (o.start.to_string(), o.region.to_string(), o.overlap)).collect()
+        };
+
+        // 1.0.0.0/16 spans four ranges with gaps between them
+        let overlaps = summary(searcher.search_prefix("1.0.0.0/16").unwrap());
+        assert_eq!(
+            overlaps,
+            [
+                ("1.0.1.0".to_string(), "中国–福建\t电信".to_string(), 256),
+                ("1.0.2.0".to_string(), "中国–福建\t电信".to_string(), 512),
+                ("1.0.8.0".to_string(), "中国–广东\t电信".to_string(), 2048),
+                ("1.0.32.0".to_string(), "中国–广东\t电信".to_string(), 8192),
+            ]
+        );
+
+        // A /22 inside one range is homogeneous; partial overlaps count only the block
+        let inside = searcher.search_prefix("43.180.4.0/22").unwrap();
+        assert_eq!(inside.len(), 1);
+        assert_eq!((inside[0].start.to_string(), inside[0].end.to_string()), ("43.176.0.0".to_string(), "43.191.255.255".to_string()));
+        assert_eq!((&*inside[0].region, inside[0].overlap), ("日本", 1024));
+        assert_eq!(summary(searcher.search_range("1.0.3.128", "1.0.8.9").unwrap()), [
+            ("1.0.2.0".to_string(), "中国–福建\t电信".to_string(), 128),
+            ("1.0.8.0".to_string(), "中国–广东\t电信".to_string(), 10),
+        ]);
+
+        // Host bits are ignored, a bare address is a single address
+        assert_eq!(searcher.search_prefix("8.8.8.8/24").unwrap()[0].overlap, 256);
+        assert_eq!(searcher.search_prefix("8.8.8.8").unwrap()[0].overlap, 1);
+        assert_eq!(searcher.search_prefix("0.0.0.0/0").unwrap().len(), common::SAMPLE_V4.len());
+        assert!(searcher.search_prefix("1.0.4.0/23").unwrap().is_empty());
+        assert!(searcher.search_range("255.0.0.0", "255.255.255.255").unwrap().is_empty());
+
+        assert_eq!(searcher.search_prefix("1.0.0.0/33").unwrap_err().code(), "INVALID_OPTION");
+        assert_eq!(searcher.search_prefix("1.0.0/8").unwrap_err().code(), "INVALID_IP");
+        assert_eq!(searcher.search_prefix("::/0").unwrap_err().code(), "IP_TYPE_MISMATCH");
+        assert_eq!(searcher.search_range("1.0.2.0", "1.0.1.0").unwrap_err().code(), "INVALID_OPTION");
+    }
+
+    // A range ending before it starts is reported once the scan reaches it
+    let reversed = common::build_db(&[("1.0.0.0", "1.0.0.255", "美国"), ("1.0.2.0", "1.0.1.0", "日本")]);
+    let searcher = DbSearcher::new(reversed, common::TEST_KEY).unwrap();
+    assert_eq!(searcher.search_prefix("1.0.0.0/24").unwrap().len(), 1);
+    let err = searcher.search_prefix("0.0.0.0/0").unwrap_err();
+    assert!(matches!(err, CzdbError::InvalidFormat { section: Section::Index, .. }), "{err}");
+
+    let searcher = DbSearcher::new(common::sample_v6(), common::TEST_KEY).unwrap();
+    let all = searcher.search_prefix("::/0").unwrap();
+    assert_eq!(all.len(), common::SAMPLE_V6.len());
+    for (overlap, (start, _, region)) in all.iter().zip(common::SAMPLE_V6) {
+        assert_eq!((overlap.start.to_string(), &*overlap.region), (start.to_string(), *region));
+    }
+}
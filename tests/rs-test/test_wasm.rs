@@ -0,0 +1,272 @@
+//! wasm-bindgen tests for the JS bindings, run under Node:
+//! `cargo test --target wasm32-unknown-unknown --test test_wasm`
+#![cfg(target_arch = "wasm32")]
+
+mod common;
+
+use czdb_rs::{create_searcher, CzdbSearcher};
+use js_sys::{Array, ArrayBuffer, DataView, Int32Array, Object, Promise, Reflect, Uint8Array};
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_test::*;
+
+fn to_uint8_array(bytes: &[u8]) -> Uint8Array {
+    Uint8Array::from(bytes)
+}
+
+/// Test the synchronous constructor with both modes
+#[wasm_bindgen_test]
+fn test_constructor() {
+    let searcher = CzdbSearcher::new(common::sample_v4(), common::TEST_KEY).unwrap();
+    assert_eq!(searcher.search_mode(), 0);
+    assert_eq!(searcher.search("1.0.8.8").unwrap(), "中国–广东\t电信");
+
+    let searcher = CzdbSearcher::new_with_mode(common::sample_v4(), common::TEST_KEY, 1).unwrap();
+    assert_eq!(searcher.search_mode(), 1);
+    assert_eq!(searcher.search("1.0.8.8").unwrap(), "中国–广东\t电信");
+}
+
+/// Test the async initializer with an ArrayBuffer
+#[wasm_bindgen_test]
+async fn test_create_from_array_buffer() {
+    let buffer: ArrayBuffer = to_uint8_array(&common::sample_v4()).buffer();
+    let searcher = create_searcher(buffer.into(), common::TEST_KEY.to_string(), None)
+        .await
+        .unwrap();
+    assert_eq!(searcher.search("8.8.8.8").unwrap(), "美国");
+}
+
+/// Test the async initializer with a Uint8Array view into a larger buffer
+#[wasm_bindgen_test]
+async fn test_create_from_uint8_array_view() {
+    let mut padded = vec![0xFFu8; 8];
+    padded.extend_from_slice(&common::sample_v6());
+    let whole = to_uint8_array(&padded);
+    let view = whole.subarray(8, whole.length());
+
+    let searcher = create_searcher(view.into(), common::TEST_KEY.to_string(), Some(1))
+        .await
+        .unwrap();
+    assert_eq!(searcher.search_mode(), 1);
+    assert_eq!(searcher.search("240e:1::1").unwrap(), "中国\t中国电信");
+}
+
+/// Test the async initializer with a promise, as returned by `fetch().arrayBuffer()`
+#[wasm_bindgen_test]
+async fn test_create_from_promise() {
+    let buffer: ArrayBuffer = to_uint8_array(&common::sample_v4()).buffer();
+    let promise = Promise::resolve(&buffer.into());
+    let searcher = create_searcher(promise.into(), common::TEST_KEY.to_string(), None)
+        .await
+        .unwrap();
+    assert_eq!(searcher.search("114.114.114.114").unwrap(), "中国–江苏–南京\t信风网络");
+}
+
+/// Test that unsupported inputs are rejected
+#[wasm_bindgen_test]
+async fn test_create_rejects_invalid_input() {
+    let result = create_searcher(JsValue::from_str("czdb"), common::TEST_KEY.to_string(), None).await;
+    assert!(result.is_err());
+
+    let garbage = to_uint8_array(&[1, 2, 3]);
+    let result = create_searcher(garbage.into(), common::TEST_KEY.to_string(), None).await;
+    assert!(result.is_err());
+
+    // A view whose length is not a number is invalid input, not an empty buffer
+    let view = DataView::new(&to_uint8_array(&common::sample_v4()).buffer(), 0, common::sample_v4().len());
+    let attributes = Object::new();
+    Reflect::set(&attributes, &"value".into(), &"many".into()).unwrap();
+    Reflect::define_property(&view, &"byteLength".into(), &attributes).unwrap();
+    let err = create_searcher(view.into(), common::TEST_KEY.to_string(), None).await.err().unwrap();
+    assert_eq!(Reflect::get(&err, &"code".into()).unwrap().as_string().unwrap(), "INVALID_INPUT");
+}
+
+fn field(obj: &JsValue, name: &str) -> String {
+    Reflect::get(obj, &name.into()).unwrap().as_string().unwrap()
+}
+
+/// Test searchRecord returns a plain object with split fields
+#[wasm_bindgen_test]
+fn test_search_record() {
+    let searcher = CzdbSearcher::new(common::sample_v4(), common::TEST_KEY).unwrap();
+
+    let record: JsValue = searcher.search_record("101.226.1.1", None).unwrap().unwrap().into();
+    assert_eq!(field(&record, "country"), "中国");
+    assert_eq!(field(&record, "province"), "上海");
+    assert_eq!(field(&record, "city"), "上海");
+    assert_eq!(field(&record, "isp"), "电信");
+    assert_eq!(field(&record, "start"), "101.226.0.0");
+    assert_eq!(field(&record, "end"), "101.226.255.255");
+    assert_eq!(field(&record, "raw"), "中国–上海–上海\t电信");
+    assert_eq!(field(&record, "countryCode"), "CN");
+    assert_eq!(field(&record, "countryAlpha3"), "CHN");
+    assert_eq!(field(&record, "subdivisionCode"), "CN-SH");
+    assert_eq!(field(&record, "carrier"), "chinatelecom");
+    assert_eq!(field(&record, "ispCategory"), "residential");
+
+    let record: JsValue = searcher.search_record("43.180.0.1", None).unwrap().unwrap().into();
+    assert_eq!(field(&record, "country"), "日本");
+    assert_eq!(field(&record, "province"), "");
+    assert_eq!(field(&record, "isp"), "");
+    assert_eq!(field(&record, "countryCode"), "JP");
+    assert_eq!(field(&record, "subdivisionCode"), "");
+    assert_eq!(field(&record, "carrier"), "");
+    assert_eq!(field(&record, "ispCategory"), "unknown");
+
+    assert!(searcher.search_record("1.0.4.1", None).unwrap().is_none());
+    assert!(searcher.search_record("not an ip", None).is_err());
+}
+
+/// Test searchBatchRecords keeps positions and yields undefined for misses
+#[wasm_bindgen_test]
+fn test_search_batch_records() {
+    let searcher = CzdbSearcher::new(common::sample_v4(), common::TEST_KEY).unwrap();
+    let ips = vec!["8.8.8.8".to_string(), "1.0.4.1".to_string(), "bad".to_string(), "1.0.2.3".to_string()];
+
+    let records: Array = searcher.search_batch_records(ips, None).unwrap().unchecked_into();
+    assert_eq!(records.length(), 4);
+    assert_eq!(field(&records.get(0), "country"), "美国");
+    assert!(records.get(1).is_undefined());
+    assert!(records.get(2).is_undefined());
+    assert_eq!(field(&records.get(3), "province"), "福建");
+
+    // Addresses of the other family are invalid too
+    let records: Array = searcher.search_batch_records(vec!["::1".to_string()], None).unwrap().unchecked_into();
+    assert!(records.get(0).is_undefined());
+
+    // A corrupt region record is an error, not a miss
+    let mut data = common::sample_v4();
+    let pos = data.windows(8).position(|w| w == [8, 8, 8, 0, 8, 8, 8, 255]).unwrap();
+    data[pos + 8..pos + 12].copy_from_slice(&u32::MAX.to_le_bytes());
+    let searcher = CzdbSearcher::new(data, common::TEST_KEY).unwrap();
+    let ips = vec!["1.0.2.3".to_string(), "8.8.8.8".to_string()];
+    let error: JsValue = searcher.search_batch_records(ips, None).err().unwrap();
+    assert_eq!(Reflect::get(&error, &"code".into()).unwrap().as_string().unwrap(), "INVALID_FORMAT");
+}
+
+/// Test record names in the searcher's language and a per-call override
+#[wasm_bindgen_test]
+fn test_record_language() {
+    let mut searcher = CzdbSearcher::new(common::sample_v4(), common::TEST_KEY).unwrap();
+    assert_eq!(searcher.language(), "zh-Hans");
+
+    let record: JsValue = searcher.search_record("101.226.1.1", Some("en".to_string())).unwrap().unwrap().into();
+    assert_eq!(field(&record, "country"), "China");
+    assert_eq!(field(&record, "province"), "Shanghai");
+    assert_eq!(field(&record, "city"), "Shanghai");
+    assert_eq!(field(&record, "isp"), "电信");
+    assert_eq!(field(&record, "raw"), "中国–上海–上海\t电信");
+
+    searcher.set_language("zh-Hant").unwrap();
+    assert_eq!(searcher.language(), "zh-Hant");
+    let ips = vec!["1.0.8.8".to_string(), "8.8.8.8".to_string()];
+    let records: Array = searcher.search_batch_records(ips, None).unwrap().unchecked_into();
+    assert_eq!(field(&records.get(0), "country"), "中國");
+    assert_eq!(field(&records.get(0), "province"), "廣東");
+    assert_eq!(field(&records.get(1), "country"), "美國");
+
+    let record: JsValue = searcher.search_record("8.8.8.8", Some("pinyin".to_string())).unwrap().unwrap().into();
+    assert_eq!(field(&record, "country"), "United States");
+
+    let error: JsValue = searcher.set_language("fr").unwrap_err();
+    assert_eq!(Reflect::get(&error, &"code".into()).unwrap().as_string().unwrap(), "INVALID_OPTION");
+    assert!(searcher.search_record("8.8.8.8", Some("xx".to_string())).is_err());
+}
+
+/// Test records have the same levels in every language
+#[wasm_bindgen_test]
+fn test_record_levels_across_languages() {
+    let ranges = [
+        ("1.0.0.0", "1.0.0.255", "中国–北京–海淀区\t联通"),
+        ("1.0.1.0", "1.0.1.255", "福建省福州市 电信"),
+    ];
+    let searcher = CzdbSearcher::new(common::build_db(&ranges), common::TEST_KEY).unwrap();
+    let levels = |ip: &str, language: &str| -> [String; 4] {
+        let record: JsValue = searcher.search_record(ip, Some(language.to_string())).unwrap().unwrap().into();
+        ["country", "province", "city", "isp"].map(|key| field(&record, key))
+    };
+
+    assert_eq!(levels("1.0.0.1", "zh"), ["中国", "北京", "北京", "联通"]);
+    assert_eq!(levels("1.0.0.1", "en"), ["China", "Beijing", "Beijing", "联通"]);
+    assert_eq!(levels("1.0.0.1", "zh-Hant"), ["中國", "北京", "北京", "联通"]);
+    assert_eq!(levels("1.0.1.1", "zh"), ["中国", "福建", "福州市", "电信"]);
+    assert_eq!(levels("1.0.1.1", "en"), ["China", "Fujian", "Fuzhou", "电信"]);
+}
+
+/// Test searchBatchDetailed reports per-item error codes
+#[wasm_bindgen_test]
+fn test_search_batch_detailed() {
+    let searcher = CzdbSearcher::new(common::sample_v4(), common::TEST_KEY).unwrap();
+    let ips = vec!["8.8.8.8".to_string(), "bad".to_string(), "::1".to_string(), "1.0.4.1".to_string()];
+
+    let batch: JsValue = searcher.search_batch_detailed(ips).into();
+    let results: Array = Reflect::get(&batch, &"results".into()).unwrap().unchecked_into();
+    let errors: Array = Reflect::get(&batch, &"errors".into()).unwrap().unchecked_into();
+
+    assert_eq!(results.get(0).as_string().unwrap(), "美国");
+    assert!(results.get(1).is_null());
+    assert!(results.get(2).is_null());
+    assert_eq!(results.get(3).as_string().unwrap(), "Unknown");
+
+    assert_eq!(errors.length(), 2);
+    assert_eq!(Reflect::get(&errors.get(0), &"index".into()).unwrap().as_f64(), Some(1.0));
+    assert_eq!(field(&errors.get(0), "code"), "INVALID_IP");
+    assert_eq!(Reflect::get(&errors.get(1), &"index".into()).unwrap().as_f64(), Some(2.0));
+    assert_eq!(field(&errors.get(1), "code"), "IP_TYPE_MISMATCH");
+}
+
+/// Test that unknown search modes are rejected instead of falling back to Memory
+#[wasm_bindgen_test]
+async fn test_invalid_mode_rejected() {
+    assert!(CzdbSearcher::new_with_mode(common::sample_v4(), common::TEST_KEY, 3).is_err());
+
+    let bytes = to_uint8_array(&common::sample_v4());
+    let result = create_searcher(bytes.into(), common::TEST_KEY.to_string(), Some(7)).await;
+    assert!(result.is_err());
+}
+
+/// Test typed-array batch search for IPv4 and IPv6
+#[wasm_bindgen_test]
+fn test_search_batch_typed_arrays() {
+    let searcher = CzdbSearcher::new(common::sample_v4(), common::TEST_KEY).unwrap();
+    let batch: JsValue = searcher.search_batch_u32(&[0x0808_0808, 0x0100_0401, 0x0808_0801]).unwrap().into();
+    let ids: Int32Array = Reflect::get(&batch, &"ids".into()).unwrap().unchecked_into();
+    let regions: Array = Reflect::get(&batch, &"regions".into()).unwrap().unchecked_into();
+    assert_eq!(ids.to_vec(), vec![0, -1, 0]);
+    assert_eq!(regions.length(), 1);
+    assert_eq!(regions.get(0).as_string().unwrap(), "美国");
+
+    let searcher = CzdbSearcher::new(common::sample_v6(), common::TEST_KEY).unwrap();
+    let mut packed = Vec::new();
+    for ip in ["2001:da8::1", "2001:4860::8888", "2001:250::1"] {
+        packed.extend_from_slice(&ip.parse::<std::net::Ipv6Addr>().unwrap().octets());
+    }
+    let batch: JsValue = searcher.search_batch_v6(&packed).unwrap().into();
+    let ids: Int32Array = Reflect::get(&batch, &"ids".into()).unwrap().unchecked_into();
+    assert_eq!(ids.to_vec(), vec![0, 1, 0]);
+
+    assert!(searcher.search_batch_v6(&packed[..15]).is_err());
+    assert!(searcher.search_batch_u32(&[1]).is_err());
+}
+
+/// Test thrown errors carry a stable code and the failing section
+#[wasm_bindgen_test]
+fn test_error_codes() {
+    let data = common::sample_v4();
+    let err = CzdbSearcher::new(data[..20].to_vec(), common::TEST_KEY).err().unwrap();
+    assert_eq!(field(&err, "name"), "CzdbError");
+    assert_eq!(field(&err, "code"), "TRUNCATED");
+    assert_eq!(field(&err, "section"), "hyper header");
+    assert_eq!(Reflect::get(&err, &"offset".into()).unwrap().as_f64(), Some(12.0));
+
+    // base64 of "fedcba9876543210"
+    let err = CzdbSearcher::new(data.clone(), "ZmVkY2JhOTg3NjU0MzIxMA==").err().unwrap();
+    assert_eq!(field(&err, "code"), "WRONG_KEY");
+
+    let searcher = CzdbSearcher::new(data, common::TEST_KEY).unwrap();
+    let err = searcher.search("not an ip").unwrap_err();
+    assert_eq!(field(&err, "code"), "INVALID_IP");
+    let err = searcher.search("::1").unwrap_err();
+    assert_eq!(field(&err, "code"), "IP_TYPE_MISMATCH");
+    let err = searcher.search_batch_v6(&[0; 15]).err().unwrap();
+    assert_eq!(field(&err, "code"), "INVALID_INPUT");
+}